@@ -1,6 +1,38 @@
-use log::error;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use glam::IVec2;
+use log::{debug, error, info, warn};
 use river_layout_toolkit::{GeneratedLayout, Layout, Rectangle};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A direction along the layout area.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
 
+/// An edge of the usable area.
+///
+/// Layout coordinates follow river's convention: the origin is the top-left
+/// corner of the usable area, with x increasing to the right and y increasing
+/// downward. `Top` is therefore at y = 0, and `Bottom` at the usable height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Edge {
     Left,
     Right,
@@ -8,6 +40,150 @@ pub enum Edge {
     Top,
 }
 
+/// Parses an edge from its name, as in `set-main-location`, ignoring case.
+impl FromStr for Edge {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" => Ok(Edge::Left),
+            "right" => Ok(Edge::Right),
+            "top" => Ok(Edge::Top),
+            "bottom" => Ok(Edge::Bottom),
+            _ => Err(Error::InvalidArgument("location")),
+        }
+    }
+}
+
+/// Formats an edge as its lowercase name, which `FromStr` accepts.
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Edge::Left => "left",
+            Edge::Right => "right",
+            Edge::Bottom => "bottom",
+            Edge::Top => "top",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl fmt::Display for Corner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        })
+    }
+}
+
+/// Space along each edge of an area, in pixels.
+///
+/// With the `serde` feature, this is either a single number for every edge,
+/// or a table of the edges, which default to 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "PaddingSpec"))]
+pub struct Padding {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl Padding {
+    /// The same padding along every edge.
+    pub fn uniform(padding: i32) -> Self {
+        Self {
+            top: padding,
+            right: padding,
+            bottom: padding,
+            left: padding,
+        }
+    }
+
+    /// Total padding across the width of an area.
+    pub fn horizontal(&self) -> i32 {
+        self.left + self.right
+    }
+
+    /// Total padding across the height of an area.
+    pub fn vertical(&self) -> i32 {
+        self.top + self.bottom
+    }
+
+    /// The padding with `f` applied to each edge.
+    fn map(self, f: impl Fn(i32) -> i32) -> Self {
+        Self {
+            top: f(self.top),
+            right: f(self.right),
+            bottom: f(self.bottom),
+            left: f(self.left),
+        }
+    }
+}
+
+impl From<i32> for Padding {
+    fn from(padding: i32) -> Self {
+        Self::uniform(padding)
+    }
+}
+
+/// The forms that [`Padding`] may be written in.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PaddingSpec {
+    Uniform(i32),
+    Edges {
+        #[serde(default)]
+        top: i32,
+        #[serde(default)]
+        right: i32,
+        #[serde(default)]
+        bottom: i32,
+        #[serde(default)]
+        left: i32,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl From<PaddingSpec> for Padding {
+    fn from(spec: PaddingSpec) -> Self {
+        match spec {
+            PaddingSpec::Uniform(padding) => Self::uniform(padding),
+            PaddingSpec::Edges {
+                top,
+                right,
+                bottom,
+                left,
+            } => Self {
+                top,
+                right,
+                bottom,
+                left,
+            },
+        }
+    }
+}
+
+/// With the `serde` feature, this can be serialized and deserialized, with
+/// the default value for every field that is left out.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 #[non_exhaustive]
 pub struct Config {
     /// The main area will extend out from this edge.
@@ -48,13 +224,255 @@ pub struct Config {
     pub secondary_window_size: f32,
 
     /// Padding around the edge of the layout area, in pixels.
-    pub outer_padding: i32,
+    pub outer_padding: Padding,
 
     /// Padding between views, in pixels.
+    ///
+    /// This may be negative, in which case the secondary windows overlap each
+    /// other by that many pixels. The main area is still kept apart from the
+    /// secondary area, as if the padding were zero.
     pub view_padding: i32,
 
     /// Offset of the secondary window, in "number of windows".
+    ///
+    /// This is also the initial framing of the secondary area. Negative values
+    /// leave empty space before the first secondary window.
+    ///
+    /// Every set of active tags keeps its own offset, starting out at the one
+    /// configured here, so switching tags doesn't lose the scroll position.
+    ///
+    /// Scrolling only ever moves the secondary windows. The main area keeps
+    /// showing the first view in the stack (or the one chosen by `main_tag`),
+    /// so the window in it stays put while browsing the rest, and only
+    /// changes when the stack itself is reordered. A view put there by the
+    /// `promote` command only stays while scrolling with `pin_main`.
     pub scroll_offset: f32,
+
+    /// Amount to scroll by when `scroll` is given no amount, in "number of
+    /// windows".
+    pub scroll_step: f32,
+
+    /// Factor to scale every scroll amount by.
+    ///
+    /// The `set-scroll-sensitivity` command overrides this for the output it
+    /// is sent to, so that scrolling can be tuned to each output's size.
+    pub scroll_sensitivity: f32,
+
+    /// Place the main area in a corner instead of along an edge.
+    ///
+    /// When set, this overrides `main_location`. The main area takes up
+    /// `main_ratio` of both the width and the height, and the secondary windows
+    /// are split between two strips running along the edges adjacent to the
+    /// corner:
+    ///
+    /// - The _horizontal_ strip sits beside the main area, is as tall as the
+    ///   main area, and scrolls horizontally. It holds the first half of the
+    ///   secondary windows and uses `secondary_window_size` and
+    ///   `scroll_offset`.
+    ///
+    /// - The _vertical_ strip sits above or below the main area, is as wide as
+    ///   the main area, and scrolls vertically. It holds the rest of the
+    ///   secondary windows and uses `corner_window_size` and
+    ///   `corner_scroll_offset`.
+    ///
+    /// The area diagonally opposite the main area is left empty. Windows
+    /// scrolled or running into the main area stop at its edge instead.
+    pub main_corner: Option<Corner>,
+
+    /// Ratio of secondary window size to the vertical strip in corner mode.
+    ///
+    /// This is the vertical strip's equivalent of `secondary_window_size`.
+    pub corner_window_size: f32,
+
+    /// Offset of the vertical strip in corner mode, in "number of windows".
+    pub corner_scroll_offset: f32,
+
+    /// Stack the secondary windows when they would be smaller than this many
+    /// pixels along the scroll axis.
+    ///
+    /// When stacked, a single secondary window fills the whole secondary area
+    /// and the rest are collapsed, like a monocle layout. Scrolling cycles
+    /// through which window is shown, rounding `scroll_offset` to the nearest
+    /// window.
+    ///
+    /// This doesn't apply in corner mode.
+    pub stack_when_crowded: Option<i32>,
+
+    /// Letterbox views to their preferred aspect ratio, as set by the
+    /// `set-view-aspect` command.
+    ///
+    /// Each such view is shrunk to its aspect ratio and centered within the
+    /// space it would otherwise fill.
+    pub respect_client_aspect: bool,
+
+    /// Size of the main area while minimized by `minimize-main` or
+    /// `peek_main`, in pixels.
+    ///
+    /// This replaces the size given by `main_ratio`, leaving the main window
+    /// peeking out as a thin dock along its edge. This doesn't apply in corner
+    /// mode.
+    pub dock_size: i32,
+
+    /// Scroll the secondary area along the same axis as the main split.
+    ///
+    /// Instead of running alongside the main area, the secondary windows
+    /// continue on from it: with a `Left` main location, they fill the
+    /// remaining width side by side and scroll _horizontally_, and with a
+    /// `Top` main location they are stacked below it and scroll _vertically_.
+    /// The `secondary_window_size` then applies to the size of the secondary
+    /// area along that axis, rather than the whole layout area.
+    ///
+    /// This doesn't apply in corner mode.
+    pub stacked: bool,
+
+    /// Scroll the secondary area along this axis, regardless of
+    /// `main_location`.
+    ///
+    /// Scrolling perpendicular to the main split is the default behavior, and
+    /// scrolling along it is the same as `stacked`. When set, this takes
+    /// precedence over `stacked`. This doesn't apply in corner mode.
+    pub scroll_axis: Option<Axis>,
+
+    /// Reserve the main area for views on these tags.
+    ///
+    /// The first view whose tags (as set by `set-view-tags`) intersect this
+    /// mask is placed in the main area, even if it isn't first in the stack.
+    /// The views before it move down into the secondary area.
+    pub main_tag: Option<u32>,
+
+    /// Give the main area this exact size, in pixels, instead of sizing it by
+    /// `main_ratio`.
+    ///
+    /// The main area stays against `main_location`, centered along that edge,
+    /// and the secondary area fills the rest. A size larger than the layout
+    /// area is clamped to fit. This doesn't apply in corner mode.
+    pub main_fixed_size: Option<IVec2>,
+
+    /// Start the secondary windows next to the main area, even when it is on
+    /// the `Right` or `Bottom`.
+    ///
+    /// This only matters when the secondary area scrolls along the main split
+    /// (see `stacked`). The secondary windows normally run from the top-left
+    /// of the secondary area, which for a `Right` or `Bottom` main location
+    /// is the side farthest from the main area. With this enabled, they run
+    /// outward from the main area instead, as they do for `Left` and `Top`.
+    pub secondary_from_main: bool,
+
+    /// Scroll to the end of the secondary area whenever a view is added, so
+    /// that the newest window is always shown.
+    ///
+    /// This assumes new views are added to the end of the stack.
+    pub follow_new: bool,
+
+    /// Short names for commands, for less verbose keybindings.
+    ///
+    /// When a command starts with one of these names, the name is replaced
+    /// with the command it stands for, and any further arguments are appended
+    /// to it. More aliases can be added with the `alias` command.
+    pub aliases: HashMap<String, String>,
+
+    /// Treat paddings as logical pixels, scaling them by the scale of each
+    /// output, as set by the `set-output-scale` command.
+    pub logical_padding: bool,
+
+    /// Shrink the main area to this aspect ratio, centered within the space
+    /// `main_ratio` gives it.
+    ///
+    /// `main_ratio` then only limits the size of the main area, and the
+    /// secondary area stays where it is. This doesn't apply in corner mode.
+    pub main_aspect: Option<f32>,
+
+    /// Keep `scroll_offset` between the first and last secondary windows.
+    ///
+    /// Scrolling past either end of the secondary windows then stops there,
+    /// instead of scrolling on into empty space. This doesn't apply to
+    /// `corner_scroll_offset`.
+    pub clamp_scroll: bool,
+
+    /// Loop the secondary windows, so that scrolling past the last one brings
+    /// the first one back around, and the other way around.
+    ///
+    /// Secondary windows too few to fill the secondary area, with one more
+    /// straddling the seam of the loop, grow until they do, so that the loop
+    /// never leaves a gap.
+    ///
+    /// This takes precedence over `clamp_scroll`. It doesn't apply in corner
+    /// mode, or while the secondary windows are stacked.
+    pub wrap: bool,
+
+    /// Collapse secondary windows scrolled entirely out of the layout area.
+    ///
+    /// river still has to map and render windows laid out off-screen, so
+    /// this shrinks them to zero size instead, at the nearest point on the
+    /// edge of the layout area.
+    pub hide_offscreen: bool,
+
+    /// Scale of secondary windows at the ends of the secondary area, relative
+    /// to those at its center.
+    ///
+    /// Secondary windows shrink as they move away from the center of the
+    /// secondary area, like items turning away on a carousel, and are centered
+    /// in the space they leave. `1.0` keeps all of them at full size. This
+    /// doesn't apply in corner mode, or while the secondary windows are
+    /// stacked.
+    pub focus_scale: f32,
+
+    /// Keep the main area minimized to `dock_size` by default, so that it only
+    /// peeks in from its edge while the secondary area takes up the rest.
+    ///
+    /// The `reveal-main` command slides the main area fully in, and back out
+    /// again.
+    pub peek_main: bool,
+
+    /// Number of views in the main area.
+    ///
+    /// The first views in the stack share the main area, divided evenly along
+    /// the scroll axis, and the rest scroll through the secondary area. With
+    /// fewer views than this, all of them are in the main area. This doesn't
+    /// apply in corner mode.
+    pub main_count: u32,
+
+    /// Shrink every secondary window to this aspect ratio, centered within the
+    /// space `secondary_window_size` gives it.
+    ///
+    /// This keeps the secondary windows the same shape as the secondary area
+    /// is resized. It doesn't apply in corner mode, or while the secondary
+    /// windows are stacked.
+    pub secondary_aspect: Option<f32>,
+
+    /// Lay out the secondary windows as a rail of thumbnails along the bottom
+    /// of the layout area, with the main area filling the rest above it.
+    ///
+    /// This takes precedence over `main_location` and `main_corner`. The rail
+    /// scrolls horizontally, and `secondary_window_size` applies to its
+    /// width.
+    pub thumbnail_rail: bool,
+
+    /// Height of the thumbnail rail, in pixels.
+    pub rail_height: i32,
+
+    /// Smallest size of secondary windows along the scroll axis, in pixels,
+    /// showing fewer of them at once instead of shrinking them further.
+    ///
+    /// This is ignored when the secondary area is too small for even one
+    /// window of this size.
+    pub min_view_size: i32,
+
+    /// Treat paddings as thousandths of the smaller dimension of each output
+    /// instead of pixels, so that they take up the same fraction of any
+    /// output.
+    ///
+    /// This takes precedence over `logical_padding`, since a fraction of an
+    /// output is the same whatever its scale.
+    pub gaps_scale_with_output: bool,
+
+    /// Keep the view put in the main area by the `promote` command there
+    /// while scrolling.
+    ///
+    /// Otherwise, scrolling returns the main area to the view it would show
+    /// anyway, so that promoting a secondary window only previews it until
+    /// browsing on.
+    pub pin_main: bool,
 }
 
 impl Default for Config {
@@ -63,197 +481,2293 @@ impl Default for Config {
             main_location: Edge::Left,
             main_ratio: 0.6,
             secondary_window_size: 0.5,
-            outer_padding: 6,
+            outer_padding: Padding::uniform(6),
             view_padding: 6,
             scroll_offset: 0.0,
+            scroll_step: 1.0,
+            scroll_sensitivity: 1.0,
+            main_corner: None,
+            corner_window_size: 0.5,
+            corner_scroll_offset: 0.0,
+            stack_when_crowded: None,
+            respect_client_aspect: false,
+            dock_size: 24,
+            stacked: false,
+            scroll_axis: None,
+            main_tag: None,
+            main_fixed_size: None,
+            secondary_from_main: false,
+            follow_new: false,
+            aliases: HashMap::new(),
+            logical_padding: false,
+            main_aspect: None,
+            clamp_scroll: false,
+            wrap: false,
+            hide_offscreen: false,
+            focus_scale: 1.0,
+            peek_main: false,
+            main_count: 1,
+            secondary_aspect: None,
+            thumbnail_rail: false,
+            rail_height: 120,
+            min_view_size: 0,
+            gaps_scale_with_output: false,
+            pin_main: false,
         }
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-#[non_exhaustive]
-pub enum Error {
-    #[error("unknown command: {0:?}")]
-    UnknownCommand(String),
+impl Config {
+    /// A builder for a configuration, starting from the default one.
+    ///
+    /// ```
+    /// use river_carousel_layout::{Config, Edge};
+    ///
+    /// let config = Config::builder()
+    ///     .main_location(Edge::Top)
+    ///     .main_ratio(0.7)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.main_ratio, 0.7);
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Self::default(),
+        }
+    }
 
-    #[error("missing argument: {0:?}")]
-    MissingArgument(&'static str),
+    /// Loads a configuration from a TOML file, falling back to the default
+    /// configuration if there is no such file.
+    ///
+    /// Keys are the names of the fields, and fields that are left out keep
+    /// their default values. Edges, axes, and corners are given in lowercase,
+    /// such as `"left"`, `"vertical"`, and `"top-left"`, and sizes as
+    /// `[width, height]` arrays:
+    ///
+    /// ```toml
+    /// main_location = "top"
+    /// main_ratio = 0.7
+    /// main_fixed_size = [800, 600]
+    ///
+    /// [aliases]
+    /// next = "scroll 1"
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(Error::ReadConfig {
+                    path: path.into(),
+                    source,
+                })
+            }
+        };
+        let config: Self = toml::from_str(&contents).map_err(|source| Error::ParseConfig {
+            path: path.into(),
+            source,
+        })?;
 
-    #[error("invalid value for argument {0:?}")]
-    InvalidArgument(&'static str),
+        ConfigBuilder::from(config).build()
+    }
+
+    /// A configuration approximating rivertile's default layout, as a starting
+    /// point when migrating from it.
+    ///
+    /// rivertile applies its view padding on every side of every view, on top
+    /// of the outer padding, so both the gaps between views and the gaps at the
+    /// edge of the output are twice its `view-padding` of 6. Matching that
+    /// here means doubling both paddings.
+    ///
+    /// For three views, the geometry matches rivertile's to within a couple of
+    /// pixels (`main_ratio` is applied after padding is removed, rather than
+    /// before). With any other number of views, rivertile resizes the stack
+    /// to fit them, while the secondary area here keeps its window size and
+    /// scrolls instead.
+    pub fn rivertile() -> Self {
+        Self {
+            main_location: Edge::Left,
+            main_ratio: 0.6,
+            secondary_window_size: 0.5,
+            outer_padding: Padding::uniform(12),
+            view_padding: 12,
+            ..Default::default()
+        }
+    }
 }
 
-pub struct Carousel {
+/// Builds a [`Config`], checking that its settings are within range.
+///
+/// Settings that aren't set keep their default values.
+pub struct ConfigBuilder {
     config: Config,
 }
 
-impl Carousel {
-    pub fn new(config: Config) -> Self {
+/// Continues building from an existing configuration, such as one loaded by
+/// [`Config::from_file`].
+impl From<Config> for ConfigBuilder {
+    fn from(config: Config) -> Self {
         Self { config }
     }
+}
 
-    fn user_cmd_inner(
-        &mut self,
-        cmd: String,
-        tags: Option<u32>,
-        output: &str,
-    ) -> Result<(), Error> {
-        let _ = (tags, output);
+impl ConfigBuilder {
+    /// Sets [`Config::main_location`].
+    pub fn main_location(mut self, main_location: Edge) -> Self {
+        self.config.main_location = main_location;
+        self
+    }
 
-        let mut parts = cmd.split_whitespace();
+    /// Sets [`Config::main_ratio`], which must be between 0 and 1.
+    pub fn main_ratio(mut self, main_ratio: f32) -> Self {
+        self.config.main_ratio = main_ratio;
+        self
+    }
 
-        match parts.next().unwrap_or("") {
-            "scroll" => {
-                let amount: f32 = parts
-                    .next()
-                    .ok_or(Error::MissingArgument("amount"))?
-                    .parse()
-                    .map_err(|_| Error::InvalidArgument("amount"))?;
+    /// Sets [`Config::secondary_window_size`], which must be between 0 and 1.
+    pub fn secondary_window_size(mut self, secondary_window_size: f32) -> Self {
+        self.config.secondary_window_size = secondary_window_size;
+        self
+    }
 
-                self.config.scroll_offset += amount;
-            }
-            other => return Err(Error::UnknownCommand(other.into())),
-        }
-        Ok(())
+    /// Sets [`Config::outer_padding`], which must not be negative along any
+    /// edge.
+    ///
+    /// A single number sets the same padding along every edge.
+    pub fn outer_padding(mut self, outer_padding: impl Into<Padding>) -> Self {
+        self.config.outer_padding = outer_padding.into();
+        self
     }
-}
 
-impl Layout for Carousel {
-    type Error = Error;
+    /// Sets [`Config::view_padding`].
+    ///
+    /// Unlike `outer_padding`, this may be negative, to overlap secondary
+    /// windows.
+    pub fn view_padding(mut self, view_padding: i32) -> Self {
+        self.config.view_padding = view_padding;
+        self
+    }
 
-    const NAMESPACE: &'static str = "carousel";
+    /// Sets [`Config::scroll_offset`], which must be finite.
+    pub fn scroll_offset(mut self, scroll_offset: f32) -> Self {
+        self.config.scroll_offset = scroll_offset;
+        self
+    }
 
-    fn user_cmd(
-        &mut self,
-        cmd: String,
-        tags: Option<u32>,
-        output: &str,
-    ) -> Result<(), Self::Error> {
-        let result = self.user_cmd_inner(cmd, tags, output);
-        if let Err(e) = &result {
-            error!("{e}");
+    /// The configuration, or the first setting that is out of range.
+    pub fn build(self) -> Result<Config, Error> {
+        let config = self.config;
+
+        if !(0.0 < config.main_ratio && config.main_ratio < 1.0) {
+            return Err(Error::InvalidArgument("main_ratio"));
+        }
+        if !(0.0 < config.secondary_window_size && config.secondary_window_size < 1.0) {
+            return Err(Error::InvalidArgument("secondary_window_size"));
+        }
+        let padding = config.outer_padding;
+        if [padding.top, padding.right, padding.bottom, padding.left]
+            .iter()
+            .any(|&edge| edge < 0)
+        {
+            return Err(Error::InvalidArgument("outer_padding"));
+        }
+        if !config.scroll_offset.is_finite() {
+            return Err(Error::InvalidArgument("scroll_offset"));
+        }
+        if !config.scroll_step.is_finite() {
+            return Err(Error::InvalidArgument("scroll_step"));
         }
 
-        result
+        Ok(config)
     }
+}
 
-    fn generate_layout(
-        &mut self,
-        view_count: u32,
-        usable_width: u32,
-        usable_height: u32,
-        tags: u32,
-        output: &str,
-    ) -> Result<GeneratedLayout, Self::Error> {
-        let _ = (tags, output);
+/// Floats are hashed by their bit patterns, so that every configuration,
+/// including ones containing NaN, hashes consistently.
+impl Hash for Config {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            main_location,
+            main_ratio,
+            secondary_window_size,
+            outer_padding,
+            view_padding,
+            scroll_offset,
+            scroll_step,
+            scroll_sensitivity,
+            main_corner,
+            corner_window_size,
+            corner_scroll_offset,
+            stack_when_crowded,
+            respect_client_aspect,
+            dock_size,
+            stacked,
+            scroll_axis,
+            main_tag,
+            main_fixed_size,
+            secondary_from_main,
+            follow_new,
+            aliases,
+            logical_padding,
+            main_aspect,
+            clamp_scroll,
+            wrap,
+            hide_offscreen,
+            focus_scale,
+            peek_main,
+            main_count,
+            secondary_aspect,
+            thumbnail_rail,
+            rail_height,
+            min_view_size,
+            gaps_scale_with_output,
+            pin_main,
+        } = self;
 
-        let padded_width = usable_width as i32 - 2 * self.config.outer_padding;
-        let padded_height = usable_height as i32 - 2 * self.config.outer_padding;
+        main_location.hash(state);
+        main_ratio.to_bits().hash(state);
+        secondary_window_size.to_bits().hash(state);
+        outer_padding.hash(state);
+        view_padding.hash(state);
+        scroll_offset.to_bits().hash(state);
+        scroll_step.to_bits().hash(state);
+        scroll_sensitivity.to_bits().hash(state);
+        main_corner.hash(state);
+        corner_window_size.to_bits().hash(state);
+        corner_scroll_offset.to_bits().hash(state);
+        stack_when_crowded.hash(state);
+        respect_client_aspect.hash(state);
+        dock_size.hash(state);
+        stacked.hash(state);
+        scroll_axis.hash(state);
+        main_tag.hash(state);
+        main_fixed_size.hash(state);
+        secondary_from_main.hash(state);
+        follow_new.hash(state);
+        let mut aliases: Vec<_> = aliases.iter().collect();
+        aliases.sort();
+        aliases.hash(state);
+        logical_padding.hash(state);
+        main_aspect.map(f32::to_bits).hash(state);
+        clamp_scroll.hash(state);
+        wrap.hash(state);
+        hide_offscreen.hash(state);
+        focus_scale.to_bits().hash(state);
+        peek_main.hash(state);
+        main_count.hash(state);
+        secondary_aspect.map(f32::to_bits).hash(state);
+        thumbnail_rail.hash(state);
+        rail_height.hash(state);
+        min_view_size.hash(state);
+        gaps_scale_with_output.hash(state);
+        pin_main.hash(state);
+    }
+}
 
-        let main_split_widthwise =
-            ((padded_width - self.config.view_padding) as f32 * self.config.main_ratio) as i32;
-        let main_split_heightwise =
-            ((padded_height - self.config.view_padding) as f32 * self.config.main_ratio) as i32;
+/// Configurations are equal when they hash the same data, so that `Eq` always
+/// agrees with `Hash`.
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        HashedBytes::of(self) == HashedBytes::of(other)
+    }
+}
 
-        let secondary_split_widthwise =
-            padded_width - self.config.view_padding - main_split_widthwise;
-        let secondary_split_heightwise =
-            padded_height - self.config.view_padding - main_split_heightwise;
+impl Eq for Config {}
 
-        let main_area = match self.config.main_location {
-            Edge::Left => Rectangle {
-                x: self.config.outer_padding,
-                y: self.config.outer_padding,
-                width: main_split_widthwise.try_into().unwrap(),
-                height: padded_height.try_into().unwrap(),
-            },
-            Edge::Top => Rectangle {
-                x: self.config.outer_padding,
-                y: self.config.outer_padding,
-                width: padded_width.try_into().unwrap(),
-                height: main_split_heightwise.try_into().unwrap(),
-            },
-            Edge::Right => Rectangle {
-                x: usable_width as i32 - self.config.outer_padding - main_split_widthwise,
-                y: self.config.outer_padding,
-                width: main_split_widthwise.try_into().unwrap(),
-                height: padded_height.try_into().unwrap(),
-            },
-            Edge::Bottom => Rectangle {
-                x: self.config.outer_padding,
-                y: usable_width as i32 - self.config.outer_padding - main_split_heightwise,
-                width: padded_width.try_into().unwrap(),
-                height: main_split_heightwise.try_into().unwrap(),
-            },
-        };
+/// A hasher that keeps all of the data written to it, to compare values by
+/// what they hash.
+#[derive(PartialEq, Default)]
+struct HashedBytes(Vec<u8>);
 
-        let secondary_size_widthwise = ((padded_width + self.config.view_padding) as f32
-            * self.config.secondary_window_size) as i32
-            - self.config.view_padding;
-        let secondary_size_heightwise = ((padded_height + self.config.view_padding) as f32
-            * self.config.secondary_window_size) as i32
-            - self.config.view_padding;
+impl HashedBytes {
+    fn of(value: &impl Hash) -> Self {
+        let mut bytes = Self::default();
+        value.hash(&mut bytes);
+        bytes
+    }
+}
 
-        let secondary_base = match self.config.main_location {
-            Edge::Left => Rectangle {
-                x: self.config.outer_padding + main_split_widthwise + self.config.view_padding,
-                y: self.config.outer_padding,
-                width: secondary_split_widthwise.try_into().unwrap(),
-                height: secondary_size_heightwise.try_into().unwrap(),
-            },
-            Edge::Top => Rectangle {
-                x: self.config.outer_padding,
-                y: self.config.outer_padding + main_split_heightwise + self.config.view_padding,
-                width: secondary_size_widthwise.try_into().unwrap(),
-                height: secondary_split_heightwise.try_into().unwrap(),
-            },
-            Edge::Right => Rectangle {
-                x: self.config.outer_padding,
-                y: self.config.outer_padding,
-                width: secondary_split_widthwise.try_into().unwrap(),
-                height: secondary_size_heightwise.try_into().unwrap(),
-            },
-            Edge::Bottom => Rectangle {
-                x: self.config.outer_padding,
-                y: self.config.outer_padding,
-                width: secondary_size_widthwise.try_into().unwrap(),
-                height: secondary_split_heightwise.try_into().unwrap(),
-            },
-        };
+impl Hasher for HashedBytes {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
 
-        let secondary_stride_x = match self.config.main_location {
-            Edge::Left | Edge::Right => 0,
-            Edge::Top | Edge::Bottom => secondary_size_widthwise + self.config.view_padding,
-        };
-        let secondary_stride_y = match self.config.main_location {
-            Edge::Left | Edge::Right => secondary_size_heightwise + self.config.view_padding,
-            Edge::Top | Edge::Bottom => 0,
-        };
+    /// The 64-bit FNV-1a hash of the collected data.
+    fn finish(&self) -> u64 {
+        self.0.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+}
 
-        let scroll_x = (secondary_stride_x as f32 * self.config.scroll_offset) as i32;
-        let scroll_y = (secondary_stride_y as f32 * self.config.scroll_offset) as i32;
+/// Arbitrary configurations for fuzzing, within the same bounds as the
+/// commands that set them.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Config {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        fn pixels(u: &mut arbitrary::Unstructured) -> arbitrary::Result<i32> {
+            Ok(u.arbitrary::<i16>()?.into())
+        }
+        fn size(u: &mut arbitrary::Unstructured) -> arbitrary::Result<i32> {
+            u.int_in_range(0..=i16::MAX.into())
+        }
+        fn ratio(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(u.int_in_range(1..=u16::MAX)? as f32 / (u16::MAX as f32 + 1.0))
+        }
+        fn finite(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(Some(u.arbitrary::<f32>()?)
+                .filter(|value| value.is_finite())
+                .unwrap_or_default())
+        }
+        fn aspect(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(Some(finite(u)?.abs())
+                .filter(|&aspect| aspect > 0.0)
+                .unwrap_or(1.0))
+        }
 
-        Ok(GeneratedLayout {
-            layout_name: Self::NAMESPACE.into(),
-            views: [main_area]
-                .into_iter()
-                .chain((0i32..).map(|i| {
-                    Rectangle {
-                        x: secondary_base
-                            .x
-                            .saturating_add(secondary_stride_x.saturating_mul(i))
-                            .saturating_sub(scroll_x),
-                        y: secondary_base
-                            .y
-                            .saturating_add(secondary_stride_y.saturating_mul(i))
-                            .saturating_sub(scroll_y),
-                        width: secondary_base.width,
-                        height: secondary_base.height,
-                    }
-                }))
-                .inspect(|r| println!("{:?}", r))
-                .take(view_count as usize)
-                .collect(),
+        Ok(Self {
+            main_location: u.arbitrary()?,
+            main_ratio: ratio(u)?,
+            secondary_window_size: ratio(u)?,
+            outer_padding: Padding {
+                top: size(u)?,
+                right: size(u)?,
+                bottom: size(u)?,
+                left: size(u)?,
+            },
+            view_padding: pixels(u)?,
+            scroll_offset: finite(u)?,
+            scroll_step: finite(u)?,
+            scroll_sensitivity: finite(u)?,
+            main_corner: u.arbitrary()?,
+            corner_window_size: ratio(u)?,
+            corner_scroll_offset: finite(u)?,
+            stack_when_crowded: u.arbitrary::<bool>()?.then(|| size(u)).transpose()?,
+            respect_client_aspect: u.arbitrary()?,
+            dock_size: size(u)?,
+            stacked: u.arbitrary()?,
+            scroll_axis: u.arbitrary()?,
+            main_tag: u.arbitrary()?,
+            main_fixed_size: u
+                .arbitrary::<bool>()?
+                .then(|| Ok(IVec2::new(size(u)?, size(u)?)))
+                .transpose()?,
+            secondary_from_main: u.arbitrary()?,
+            follow_new: u.arbitrary()?,
+            aliases: u.arbitrary()?,
+            logical_padding: u.arbitrary()?,
+            main_aspect: u.arbitrary::<bool>()?.then(|| aspect(u)).transpose()?,
+            clamp_scroll: u.arbitrary()?,
+            wrap: u.arbitrary()?,
+            hide_offscreen: u.arbitrary()?,
+            focus_scale: finite(u)?,
+            peek_main: u.arbitrary()?,
+            main_count: u.int_in_range(1..=u8::MAX.into())?,
+            secondary_aspect: u.arbitrary::<bool>()?.then(|| aspect(u)).transpose()?,
+            thumbnail_rail: u.arbitrary()?,
+            rail_height: size(u)?,
+            min_view_size: size(u)?,
+            gaps_scale_with_output: u.arbitrary()?,
+            pin_main: u.arbitrary()?,
         })
     }
 }
+
+/// Entries of a map of floats, in a stable order and with the floats as bits,
+/// to be hashed.
+fn sorted_bits<K: Ord + Clone>(map: &HashMap<K, f32>) -> Vec<(K, u32)> {
+    let mut entries: Vec<_> = map
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_bits()))
+        .collect();
+    entries.sort_unstable();
+    entries
+}
+
+/// Bounds for ratios changed by relative commands, which stop short of either
+/// end so that repeatedly stepping a ratio never collapses an area entirely.
+const MIN_STEPPED_RATIO: f32 = 0.05;
+const MAX_STEPPED_RATIO: f32 = 0.95;
+
+/// Largest usable width or height that is laid out; any larger area is laid
+/// out as if it were this size.
+///
+/// No output comes anywhere close, and this leaves the layout arithmetic
+/// plenty of headroom before it could overflow an `i32`.
+const MAX_USABLE_SIZE: u32 = u16::MAX as u32;
+
+/// Largest number of views that is laid out; layouts of any more are refused.
+///
+/// Like `MAX_USABLE_SIZE`, this is far beyond anything river asks for, and
+/// keeps the strip arithmetic well clear of overflowing an `i32`.
+const MAX_VIEW_COUNT: u32 = u16::MAX as u32;
+
+/// Parts of the smaller dimension of the output that paddings are given in,
+/// with `Config::gaps_scale_with_output`.
+const GAP_FRACTION_PARTS: f32 = 1000.0;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("unknown command: {0:?}")]
+    UnknownCommand(String),
+
+    #[error("missing argument: {0:?}")]
+    MissingArgument(&'static str),
+
+    #[error("invalid value for argument {0:?}")]
+    InvalidArgument(&'static str),
+
+    #[error("cannot lay out {0} views, at most {MAX_VIEW_COUNT} are supported")]
+    TooManyViews(u32),
+
+    /// A valid command that can't be applied in the current state, which is
+    /// left unchanged.
+    #[error("cannot {operation}: {state}")]
+    State {
+        /// The command that failed.
+        operation: &'static str,
+
+        /// Why the current state doesn't allow it.
+        state: String,
+    },
+
+    #[cfg(feature = "serde")]
+    #[error("cannot read config file {path:?}: {source}")]
+    ReadConfig { path: PathBuf, source: io::Error },
+
+    #[cfg(feature = "serde")]
+    #[error("invalid config file {path:?}: {source}")]
+    ParseConfig {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+/// Counters describing the work done by the layout, reported by the `stats`
+/// command.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of layouts generated.
+    pub layouts_generated: u64,
+
+    /// Number of user commands received, including invalid ones.
+    pub commands_received: u64,
+
+    /// Time taken to generate the most recent layout.
+    pub last_generation_time: Duration,
+}
+
+/// Space to leave empty along each edge of the usable area, in pixels.
+#[derive(Default, Hash)]
+struct Insets {
+    top: u32,
+    right: u32,
+    bottom: u32,
+    left: u32,
+}
+
+/// The inputs a layout was generated from.
+struct LayoutInputs {
+    view_count: u32,
+    usable_width: u32,
+    usable_height: u32,
+    tags: u32,
+    output: String,
+}
+
+/// The layout generator.
+///
+/// This is normally handed to `river_layout_toolkit::run`, which drives it
+/// from river's layout protocol. It doesn't depend on that event loop,
+/// though, and can be driven directly through the [`Layout`] trait by anything
+/// else that generates layouts, such as a compositor embedding it:
+///
+/// - `user_cmd` applies a command, exactly as if it had been sent with
+///   `riverctl send-layout-cmd`. An invalid command is logged and returned as
+///   an error, without changing any state.
+///
+/// - `generate_layout` returns one view for each of the `view_count` views,
+///   in stack order, in coordinates relative to the top-left corner of the
+///   usable area.
+///
+/// Neither does any I/O other than logging.
+///
+/// ```
+/// use river_layout_toolkit::Layout;
+/// use river_carousel_layout::{Carousel, Config};
+///
+/// let mut layout = Carousel::new(Config::default());
+/// layout.user_cmd("scroll 1".into(), None, "eDP-1").unwrap();
+/// let generated = layout.generate_layout(3, 1920, 1080, 1, "eDP-1").unwrap();
+/// let views: Vec<_> = generated
+///     .views
+///     .iter()
+///     .map(|view| (view.x, view.y, view.width, view.height))
+///     .collect();
+/// assert_eq!(
+///     views,
+///     [(6, 6, 1141, 1068), (1153, -531, 761, 531), (1153, 6, 761, 531)],
+/// );
+/// ```
+pub struct Carousel {
+    config: Config,
+    stats: Stats,
+
+    /// Preferred aspect ratios of views, by index.
+    view_aspects: HashMap<u32, f32>,
+
+    /// Range of secondary windows requested by `show-range`, as `(start,
+    /// count)`, to be applied once the view count is known.
+    shown_range: Option<(u32, u32)>,
+
+    /// Whether the main area is shrunk to `Config::dock_size`.
+    main_minimized: bool,
+
+    /// The most recently generated layout.
+    last_layout: Option<GeneratedLayout>,
+
+    /// Length of the secondary area along its scroll axis in the last layout
+    /// of a strip, which `Config::min_view_size` fits the windows into.
+    secondary_area_size: Option<u32>,
+
+    /// Main ratio that the secondary area is sized by, when it has been
+    /// decoupled from `Config::main_ratio` by `mod-main-ratio-only` or
+    /// `set-main-ratio-only`.
+    secondary_ratio: Option<f32>,
+
+    /// Insets from the edges of the usable area, set by `set-safe-area`.
+    safe_area: Insets,
+
+    /// Views to keep showing instead of generating new layouts, set by
+    /// `freeze`.
+    frozen: Option<Vec<Rectangle>>,
+
+    /// Tags of views, by index, as set by `set-view-tags`.
+    view_tags: HashMap<u32, u32>,
+
+    /// Scroll sensitivities of outputs, by name, overriding
+    /// `Config::scroll_sensitivity`.
+    scroll_sensitivities: HashMap<String, f32>,
+
+    /// `Config::scroll_offset` and `Config::corner_scroll_offset` as
+    /// configured, restored by `reset-view`.
+    initial_scroll_offsets: (f32, f32),
+
+    /// Tags that `Config::scroll_offset` belongs to, as last given by river.
+    active_tags: Option<u32>,
+
+    /// Scroll offsets of tags other than the active ones, by tags, restored
+    /// once they are active again.
+    tag_scroll_offsets: HashMap<u32, f32>,
+
+    /// Whether all views are shown in a grid instead, toggled by `overview`
+    /// and left by `restore`.
+    overview: bool,
+
+    /// Whether the secondary area scrolls across its usual scroll axis, toggled
+    /// by `rotate-strip`.
+    strip_rotated: bool,
+
+    /// Stack index of the view put in the main area by `promote`.
+    promoted: Option<u32>,
+
+    /// Inputs of `last_layout`, to reuse it while river keeps asking for the
+    /// same one. Cleared by every user command, since any of them may change
+    /// the result.
+    cache: Option<LayoutInputs>,
+
+    /// Scales of outputs, by name, as set by `set-output-scale`.
+    output_scales: HashMap<String, f32>,
+}
+
+impl Carousel {
+    pub fn new(config: Config) -> Self {
+        Self {
+            initial_scroll_offsets: (config.scroll_offset, config.corner_scroll_offset),
+            active_tags: None,
+            tag_scroll_offsets: HashMap::new(),
+            main_minimized: config.peek_main,
+            config,
+            stats: Stats::default(),
+            view_aspects: HashMap::new(),
+            shown_range: None,
+            last_layout: None,
+            secondary_area_size: None,
+            secondary_ratio: None,
+            safe_area: Insets::default(),
+            frozen: None,
+            view_tags: HashMap::new(),
+            scroll_sensitivities: HashMap::new(),
+            overview: false,
+            strip_rotated: false,
+            promoted: None,
+            cache: None,
+            output_scales: HashMap::new(),
+        }
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// The most recently generated layout.
+    pub fn last_layout(&self) -> Option<&GeneratedLayout> {
+        self.last_layout.as_ref()
+    }
+
+    /// A hash of the configuration and of everything else that commands
+    /// change, which is the same whenever the same changes have been made.
+    ///
+    /// Being FNV-1a, it is also the same across runs and builds.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = HashedBytes::default();
+        self.config.hash(&mut hasher);
+        sorted_bits(&self.view_aspects).hash(&mut hasher);
+        self.shown_range.hash(&mut hasher);
+        self.main_minimized.hash(&mut hasher);
+        self.secondary_ratio.map(f32::to_bits).hash(&mut hasher);
+        self.safe_area.hash(&mut hasher);
+        self.frozen
+            .as_ref()
+            .map(|views| {
+                views
+                    .iter()
+                    .map(|view| (view.x, view.y, view.width, view.height))
+                    .collect::<Vec<_>>()
+            })
+            .hash(&mut hasher);
+        let mut view_tags: Vec<_> = self.view_tags.iter().collect();
+        view_tags.sort();
+        view_tags.hash(&mut hasher);
+        sorted_bits(&self.scroll_sensitivities).hash(&mut hasher);
+        self.active_tags.hash(&mut hasher);
+        sorted_bits(&self.tag_scroll_offsets).hash(&mut hasher);
+        self.overview.hash(&mut hasher);
+        self.strip_rotated.hash(&mut hasher);
+        self.promoted.hash(&mut hasher);
+        sorted_bits(&self.output_scales).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Index of the view containing the given point, according to the most
+    /// recently generated layout.
+    ///
+    /// Returns `None` if the point is in the padding between views, or outside
+    /// of all of them.
+    pub fn view_at(&self, x: i32, y: i32) -> Option<usize> {
+        let (x, y) = (x as i64, y as i64);
+        let views = self
+            .last_layout
+            .as_ref()
+            .map_or(&[][..], |layout| &layout.views);
+        views.iter().position(|view| {
+            (view.x as i64..view.x as i64 + view.width as i64).contains(&x)
+                && (view.y as i64..view.y as i64 + view.height as i64).contains(&y)
+        })
+    }
+
+    fn log_stats(&self) {
+        info!(
+            "layouts generated: {}, commands received: {}, last generation time: {}us",
+            self.stats.layouts_generated,
+            self.stats.commands_received,
+            self.stats.last_generation_time.as_micros(),
+        );
+    }
+
+    /// The scroll offset that brings the last secondary window to the end of
+    /// the secondary area, or zero if they all fit.
+    fn end_scroll_offset(&self, view_count: u32) -> f32 {
+        let secondary_count = (view_count - self.main_count(view_count)) as f32;
+        (secondary_count - self.shown_count()).max(0.0)
+    }
+
+    /// Length of the secondary windows along a secondary area of the given
+    /// length, and how many of them it shows at once.
+    ///
+    /// Windows are only sized below `Config::min_view_size` when the area is
+    /// too small for even one of that size.
+    fn secondary_window_length(&self, area_size: u32) -> (i32, f32) {
+        let padding = self.config.view_padding;
+        let shown_count = 1.0 / self.config.secondary_window_size;
+        let size = ((area_size as i32 + padding) as f32 * self.config.secondary_window_size) as i32
+            - padding;
+        let min_size = self.config.min_view_size;
+        if size < min_size && area_size as i32 >= min_size {
+            let shown_count = (area_size as i32 + padding) as f32 / (min_size + padding) as f32;
+            (min_size, shown_count)
+        } else {
+            (size, shown_count)
+        }
+    }
+
+    /// Number of secondary windows shown at once, as of the last layout.
+    fn shown_count(&self) -> f32 {
+        match self.secondary_area_size {
+            Some(area_size) => self.secondary_window_length(area_size).1,
+            None => 1.0 / self.config.secondary_window_size,
+        }
+    }
+
+    /// Switches `Config::scroll_offset` to that of the given tags, keeping the
+    /// one of the previously active tags for when they are active again.
+    fn activate_tags(&mut self, tags: u32) {
+        match self.active_tags.replace(tags) {
+            Some(previous) if previous != tags => {
+                self.tag_scroll_offsets
+                    .insert(previous, self.config.scroll_offset);
+                // Tags seen for the first time start at the beginning of
+                // the strip, whatever offset the first ones were given.
+                self.config.scroll_offset = self.tag_scroll_offsets.remove(&tags).unwrap_or(0.0);
+            }
+            // Until then, the offset belongs to the first tags seen.
+            _ => {}
+        }
+    }
+
+    /// Number of views in the main area, out of the given number of views.
+    fn main_count(&self, view_count: u32) -> u32 {
+        let main_count = match (&self.config.main_corner, self.config.thumbnail_rail) {
+            (Some(_), false) => 1,
+            _ => self.config.main_count.max(1),
+        };
+        main_count.min(view_count)
+    }
+
+    /// Commands that would bring a layout with the default configuration to
+    /// the current one, for the `dump-cmd` command.
+    ///
+    /// Only settings that can be changed by a command are included, and only
+    /// when their values are ones that the command accepts.
+    fn dump_commands(&self) -> Vec<String> {
+        let config = &self.config;
+        let default = Config::default();
+        let mut commands = Vec::new();
+
+        let is_ratio = |ratio: f32| 0.0 < ratio && ratio < 1.0;
+        let is_size = |size: i32| (0..=i16::MAX.into()).contains(&size);
+        let is_aspect = |aspect: f32| aspect.is_finite() && aspect > 0.0;
+
+        if config.main_location != default.main_location {
+            commands.push(format!("set-main-location {}", config.main_location));
+        }
+        // `set-main-ratio-only` keeps sizing the secondary area by the main
+        // ratio it replaces.
+        let secondary_ratio = self.secondary_ratio.unwrap_or(config.main_ratio);
+        if secondary_ratio != default.main_ratio && is_ratio(secondary_ratio) {
+            commands.push(format!("set-main-ratio {secondary_ratio}"));
+        }
+        if self.secondary_ratio.is_some() && is_ratio(config.main_ratio) {
+            commands.push(format!("set-main-ratio-only {}", config.main_ratio));
+        }
+        if config.secondary_window_size != default.secondary_window_size
+            && is_ratio(config.secondary_window_size)
+        {
+            commands.push(format!(
+                "set-secondary-size {}",
+                config.secondary_window_size
+            ));
+        }
+        if config.main_count != default.main_count && config.main_count > 0 {
+            commands.push(format!("set-main-count {}", config.main_count));
+        }
+        if config.view_padding != default.view_padding && i16::try_from(config.view_padding).is_ok()
+        {
+            commands.push(format!("set-view-padding {}", config.view_padding));
+        }
+        if let Some(axis) = &config.scroll_axis {
+            let axis = match axis {
+                Axis::Horizontal => "horizontal",
+                Axis::Vertical => "vertical",
+            };
+            commands.push(format!("set-scroll-axis {axis}"));
+        }
+        if let Some(aspect) = config.main_aspect.filter(|&aspect| is_aspect(aspect)) {
+            commands.push(format!("set-main-aspect {aspect}"));
+        }
+        if let Some(aspect) = config.secondary_aspect.filter(|&aspect| is_aspect(aspect)) {
+            commands.push(format!("set-secondary-aspect {aspect}"));
+        }
+        if config.scroll_step != default.scroll_step && config.scroll_step.is_finite() {
+            commands.push(format!("set-scroll-step {}", config.scroll_step));
+        }
+        // Scrolling from the default offset of zero, at the default
+        // sensitivity of one.
+        if config.scroll_offset != default.scroll_offset && config.scroll_offset.is_finite() {
+            commands.push(format!("scroll {}", config.scroll_offset));
+        }
+        if config.corner_scroll_offset != default.corner_scroll_offset
+            && config.corner_scroll_offset.is_finite()
+        {
+            commands.push(format!("corner-scroll {}", config.corner_scroll_offset));
+        }
+        if config.follow_new {
+            commands.push("follow-new".into());
+        }
+        if config.thumbnail_rail {
+            commands.push("thumbnail-rail".into());
+        }
+        if config.rail_height != default.rail_height && is_size(config.rail_height) {
+            commands.push(format!("set-rail-height {}", config.rail_height));
+        }
+        if config.min_view_size != default.min_view_size && is_size(config.min_view_size) {
+            commands.push(format!("set-min-view-size {}", config.min_view_size));
+        }
+        if config.pin_main {
+            commands.push("pin-main".into());
+        }
+
+        let mut aliases: Vec<_> = config.aliases.iter().collect();
+        aliases.sort();
+        for (name, command) in aliases {
+            commands.push(format!("alias {name} {command}"));
+        }
+
+        commands
+    }
+
+    /// A one-line summary of the layout, as of the most recently generated
+    /// one, for the `describe` command.
+    fn describe(&self) -> String {
+        let view_count = self
+            .last_layout
+            .as_ref()
+            .map_or(0, |layout| layout.views.len());
+        let secondary_count = view_count - self.main_count(view_count as u32) as usize;
+        let visible_count = (self.shown_count().round() as usize).min(secondary_count);
+        let main = match &self.config.main_corner {
+            Some(corner) => corner.to_string(),
+            None => self.config.main_location.to_string(),
+        };
+
+        format!(
+            "{}: main {main} at {:.0}%, {visible_count} of {secondary_count} visible, scroll {:.1}",
+            Self::NAMESPACE,
+            self.config.main_ratio * 100.0,
+            self.config.scroll_offset,
+        )
+    }
+
+    /// A copy of the last layout, if it can be reused for the given inputs.
+    fn cached(
+        &self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+        output: &str,
+    ) -> Option<GeneratedLayout> {
+        let cache = self.cache.as_ref()?;
+        let layout = self.last_layout.as_ref()?;
+        let hit = cache.view_count == view_count
+            && cache.usable_width == usable_width
+            && cache.usable_height == usable_height
+            && cache.tags == tags
+            && cache.output == output;
+
+        hit.then(|| GeneratedLayout {
+            layout_name: layout.layout_name.clone(),
+            views: layout.views.clone(),
+        })
+    }
+
+    fn user_cmd_inner(
+        &mut self,
+        cmd: String,
+        tags: Option<u32>,
+        output: &str,
+    ) -> Result<(), Error> {
+        let _ = tags;
+
+        // Any whitespace separates arguments, but other control characters are
+        // most likely a mistake.
+        if cmd.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+            return Err(Error::InvalidArgument("command"));
+        }
+
+        let alias = cmd
+            .split_whitespace()
+            .next()
+            .and_then(|name| self.config.aliases.get(name))
+            .cloned();
+        let cmd = match alias {
+            Some(expansion) => {
+                let args = cmd
+                    .trim_start()
+                    .split_once(char::is_whitespace)
+                    .map_or("", |(_, args)| args);
+                format!("{expansion} {args}")
+            }
+            None => cmd,
+        };
+
+        let mut parts = cmd.split_whitespace();
+
+        match parts.next().unwrap_or("") {
+            "scroll" => {
+                let amount: f32 = match parts.next() {
+                    Some(amount) => amount
+                        .parse()
+                        .ok()
+                        .filter(|amount: &f32| amount.is_finite())
+                        .ok_or(Error::InvalidArgument("amount"))?,
+                    None => self.config.scroll_step,
+                };
+                let sensitivity = self
+                    .scroll_sensitivities
+                    .get(output)
+                    .copied()
+                    .unwrap_or(self.config.scroll_sensitivity);
+
+                self.config.scroll_offset += amount * sensitivity;
+            }
+            "snap" => {
+                self.config.scroll_offset = self.config.scroll_offset.round();
+            }
+            "snap-next" => {
+                self.config.scroll_offset = self.config.scroll_offset.floor() + 1.0;
+            }
+            "snap-prev" => {
+                self.config.scroll_offset = self.config.scroll_offset.ceil() - 1.0;
+            }
+            "scroll-to" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+                let view_count = self
+                    .last_layout
+                    .as_ref()
+                    .map_or(0, |layout| layout.views.len() as u32);
+                if index >= view_count - self.main_count(view_count) {
+                    return Err(Error::InvalidArgument("index"));
+                }
+
+                // Center the window in the secondary area.
+                self.config.scroll_offset = index as f32 + 0.5 - self.shown_count() / 2.0;
+            }
+            "set-scroll-sensitivity" => {
+                let sensitivity: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("sensitivity"))?
+                    .parse()
+                    .ok()
+                    .filter(|sensitivity: &f32| sensitivity.is_finite())
+                    .ok_or(Error::InvalidArgument("sensitivity"))?;
+
+                self.scroll_sensitivities.insert(output.into(), sensitivity);
+            }
+            "set-scroll-step" => {
+                let step: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("step"))?
+                    .parse()
+                    .ok()
+                    .filter(|step: &f32| step.is_finite())
+                    .ok_or(Error::InvalidArgument("step"))?;
+
+                self.config.scroll_step = step;
+            }
+            "set-view-padding" => {
+                // Anything beyond an `i16` is surely a mistake, and could
+                // overflow the layout arithmetic.
+                let padding: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("padding"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("padding"))?;
+
+                self.config.view_padding = padding.into();
+            }
+            "corner-scroll" => {
+                let amount: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("amount"))?
+                    .parse()
+                    .ok()
+                    .filter(|amount: &f32| amount.is_finite())
+                    .ok_or(Error::InvalidArgument("amount"))?;
+
+                self.config.corner_scroll_offset += amount;
+            }
+            "set-view-aspect" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+                let aspect = parts.next().ok_or(Error::MissingArgument("aspect"))?;
+
+                if aspect == "none" {
+                    self.view_aspects.remove(&index);
+                } else {
+                    let aspect = parse_aspect(aspect).ok_or(Error::InvalidArgument("aspect"))?;
+                    self.view_aspects.insert(index, aspect);
+                }
+            }
+            "show-range" => {
+                let start: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("start"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("start"))?;
+                let count: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("count"))?
+                    .parse()
+                    .ok()
+                    .filter(|&count| count > 0)
+                    .ok_or(Error::InvalidArgument("count"))?;
+
+                self.shown_range = Some((start, count));
+            }
+            "minimize-main" => {
+                self.main_minimized = true;
+            }
+            "restore-main" => {
+                self.main_minimized = false;
+            }
+            "reveal-main" => {
+                self.main_minimized = !self.main_minimized;
+            }
+            "set-main-count" => {
+                let count: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("count"))?
+                    .parse()
+                    .ok()
+                    .filter(|&count| count > 0)
+                    .ok_or(Error::InvalidArgument("count"))?;
+
+                self.config.main_count = count;
+            }
+            "set-main-ratio" => {
+                let ratio: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("ratio"))?
+                    .parse()
+                    .ok()
+                    .filter(|ratio| 0.0 < *ratio && *ratio < 1.0)
+                    .ok_or(Error::InvalidArgument("ratio"))?;
+
+                self.config.main_ratio = ratio;
+                self.secondary_ratio = None;
+            }
+            "main-ratio" => {
+                let delta: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("delta"))?
+                    .parse()
+                    .ok()
+                    .filter(|delta: &f32| delta.is_finite())
+                    .ok_or(Error::InvalidArgument("delta"))?;
+
+                self.config.main_ratio =
+                    (self.config.main_ratio + delta).clamp(MIN_STEPPED_RATIO, MAX_STEPPED_RATIO);
+                self.secondary_ratio = None;
+            }
+            "set-secondary-size" => {
+                // As with `Config::secondary_window_size`, this is the
+                // fraction of the secondary area each window takes up, so
+                // `0.5` shows two windows at once.
+                let size: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("size"))?
+                    .parse()
+                    .ok()
+                    .filter(|size| 0.0 < *size && *size < 1.0)
+                    .ok_or(Error::InvalidArgument("size"))?;
+
+                self.config.secondary_window_size = size;
+            }
+            "secondary-size" => {
+                let delta: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("delta"))?
+                    .parse()
+                    .ok()
+                    .filter(|delta: &f32| delta.is_finite())
+                    .ok_or(Error::InvalidArgument("delta"))?;
+
+                self.config.secondary_window_size = (self.config.secondary_window_size + delta)
+                    .clamp(MIN_STEPPED_RATIO, MAX_STEPPED_RATIO);
+            }
+            "mod-main-ratio-only" => {
+                let delta: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("delta"))?
+                    .parse()
+                    .ok()
+                    .filter(|delta: &f32| delta.is_finite())
+                    .ok_or(Error::InvalidArgument("delta"))?;
+
+                self.secondary_ratio.get_or_insert(self.config.main_ratio);
+                self.config.main_ratio =
+                    (self.config.main_ratio + delta).clamp(MIN_STEPPED_RATIO, MAX_STEPPED_RATIO);
+            }
+            "set-main-ratio-only" => {
+                let ratio: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("ratio"))?
+                    .parse()
+                    .ok()
+                    .filter(|ratio| 0.0 < *ratio && *ratio < 1.0)
+                    .ok_or(Error::InvalidArgument("ratio"))?;
+
+                self.secondary_ratio.get_or_insert(self.config.main_ratio);
+                self.config.main_ratio = ratio;
+            }
+            "golden-ratio" => {
+                // 1/φ, and its complement 1 - 1/φ = 1/φ².
+                const LARGE: f32 = 0.618_034;
+                const SMALL: f32 = 1.0 - LARGE;
+
+                self.config.main_ratio = if (self.config.main_ratio - LARGE).abs() < 0.001 {
+                    SMALL
+                } else {
+                    LARGE
+                };
+                self.secondary_ratio = None;
+            }
+            "set-safe-area" => {
+                // Anything beyond a `u16` is surely a mistake, and could
+                // overflow the layout arithmetic.
+                let mut inset = |name| -> Result<u32, Error> {
+                    parts
+                        .next()
+                        .ok_or(Error::MissingArgument(name))?
+                        .parse::<u16>()
+                        .map(u32::from)
+                        .map_err(|_| Error::InvalidArgument(name))
+                };
+
+                self.safe_area = Insets {
+                    top: inset("top")?,
+                    right: inset("right")?,
+                    bottom: inset("bottom")?,
+                    left: inset("left")?,
+                };
+            }
+            "set-main-location" => {
+                let location = parts.next().ok_or(Error::MissingArgument("location"))?;
+
+                // The scroll offset is kept, so the same secondary windows
+                // stay in view along the new edge.
+                self.config.main_location = location.parse()?;
+            }
+            "set-main-aspect" => {
+                let aspect = parts.next().ok_or(Error::MissingArgument("aspect"))?;
+
+                self.config.main_aspect = if aspect == "none" {
+                    None
+                } else {
+                    Some(parse_aspect(aspect).ok_or(Error::InvalidArgument("aspect"))?)
+                };
+            }
+            "set-secondary-aspect" => {
+                let aspect = parts.next().ok_or(Error::MissingArgument("aspect"))?;
+
+                self.config.secondary_aspect = if aspect == "none" {
+                    None
+                } else {
+                    Some(parse_aspect(aspect).ok_or(Error::InvalidArgument("aspect"))?)
+                };
+            }
+            "set-scroll-axis" => {
+                self.config.scroll_axis =
+                    match parts.next().ok_or(Error::MissingArgument("axis"))? {
+                        "horizontal" => Some(Axis::Horizontal),
+                        "vertical" => Some(Axis::Vertical),
+                        "auto" => None,
+                        _ => return Err(Error::InvalidArgument("axis")),
+                    };
+            }
+            "freeze" => {
+                // Freezing before the first layout would hide every view.
+                let layout = self.last_layout.as_ref().ok_or(Error::State {
+                    operation: "freeze",
+                    state: "no layout has been generated yet".into(),
+                })?;
+                self.frozen = Some(layout.views.clone());
+            }
+            "unfreeze" => {
+                if self.frozen.take().is_none() {
+                    return Err(Error::State {
+                        operation: "unfreeze",
+                        state: "the layout isn't frozen".into(),
+                    });
+                }
+            }
+            "set-view-tags" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+                let tags: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("tags"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("tags"))?;
+
+                self.view_tags.insert(index, tags);
+            }
+            "reset-view" => {
+                // Only undo temporary changes to the view, keeping the
+                // configuration and what is known about the views.
+                (self.config.scroll_offset, self.config.corner_scroll_offset) =
+                    self.initial_scroll_offsets;
+                self.shown_range = None;
+                self.main_minimized = self.config.peek_main;
+                self.frozen = None;
+                self.overview = false;
+                self.strip_rotated = false;
+                self.promoted = None;
+            }
+            "promote" => {
+                self.promoted = match parts.next() {
+                    Some("none") => None,
+                    Some(index) => {
+                        Some(index.parse().map_err(|_| Error::InvalidArgument("index"))?)
+                    }
+                    // Without an index, promote the secondary window that the
+                    // secondary area is scrolled to.
+                    None => {
+                        let layout = self.last_layout.as_ref().ok_or(Error::State {
+                            operation: "promote",
+                            state: "no layout has been generated yet".into(),
+                        })?;
+                        let view_count = layout.views.len() as u32;
+                        let scrolled = self.config.scroll_offset.round().max(0.0) as u32;
+                        Some(
+                            self.main_count(view_count)
+                                .saturating_add(scrolled)
+                                .min(view_count.saturating_sub(1)),
+                        )
+                    }
+                };
+            }
+            "pin-main" => {
+                self.config.pin_main = !self.config.pin_main;
+            }
+            "reset" => {
+                // Return to the default configuration, optionally keeping
+                // which edge the main area is on.
+                let keep_location = match parts.next() {
+                    Some("keep-location") => true,
+                    Some(_) => return Err(Error::InvalidArgument("keep-location")),
+                    None => false,
+                };
+                let main_location = self.config.main_location;
+                self.config = Config::default();
+                self.secondary_ratio = None;
+                if keep_location {
+                    self.config.main_location = main_location;
+                }
+            }
+            "reset-scroll" => {
+                self.config.scroll_offset = 0.0;
+            }
+            "follow-new" => {
+                self.config.follow_new = !self.config.follow_new;
+            }
+            "alias" => {
+                let name = parts.next().ok_or(Error::MissingArgument("name"))?;
+                let command = parts.collect::<Vec<_>>().join(" ");
+                if command.is_empty() {
+                    return Err(Error::MissingArgument("command"));
+                }
+
+                self.config.aliases.insert(name.into(), command);
+            }
+            "set-output-scale" => {
+                let scale: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("scale"))?
+                    .parse()
+                    .ok()
+                    .filter(|scale: &f32| scale.is_finite() && *scale > 0.0)
+                    .ok_or(Error::InvalidArgument("scale"))?;
+
+                self.output_scales.insert(output.into(), scale);
+            }
+            "describe" => {
+                info!("{}", self.describe());
+            }
+            "overview" => {
+                self.overview = !self.overview;
+            }
+            "restore" => {
+                if !std::mem::take(&mut self.overview) {
+                    return Err(Error::State {
+                        operation: "restore",
+                        state: "the overview isn't shown".into(),
+                    });
+                }
+            }
+            "thumbnail-rail" => {
+                self.config.thumbnail_rail = !self.config.thumbnail_rail;
+            }
+            "set-rail-height" => {
+                let height: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("height"))?
+                    .parse()
+                    .ok()
+                    .filter(|&height| height >= 0)
+                    .ok_or(Error::InvalidArgument("height"))?;
+
+                self.config.rail_height = height.into();
+            }
+            "set-min-view-size" => {
+                let size: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("size"))?
+                    .parse()
+                    .ok()
+                    .filter(|&size| size >= 0)
+                    .ok_or(Error::InvalidArgument("size"))?;
+
+                self.config.min_view_size = size.into();
+            }
+            "rotate-strip" => {
+                self.strip_rotated = !self.strip_rotated;
+            }
+            "dump-cmd" => {
+                for command in self.dump_commands() {
+                    info!("riverctl send-layout-cmd {} '{command}'", Self::NAMESPACE);
+                }
+            }
+            "stats" => {
+                self.log_stats();
+            }
+            "state-hash" => {
+                info!("state-hash {:016x}", self.state_hash());
+            }
+            other => return Err(Error::UnknownCommand(other.into())),
+        }
+        Ok(())
+    }
+
+    fn generate_layout_inner(
+        &mut self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+        output: &str,
+    ) -> Result<GeneratedLayout, Error> {
+        let _ = (tags, output);
+
+        let last_view_count = self
+            .last_layout
+            .as_ref()
+            .map_or(0, |layout| layout.views.len() as u32);
+        if self.config.follow_new && view_count > last_view_count {
+            self.config.scroll_offset = self.end_scroll_offset(view_count);
+        }
+
+        if let Some((start, count)) = self.shown_range.take() {
+            let secondary_count = view_count - self.main_count(view_count);
+            if start.saturating_add(count) <= secondary_count {
+                self.config.secondary_window_size = 1.0 / count as f32;
+                self.config.scroll_offset = start as f32;
+            } else {
+                warn!(
+                    "cannot show {count} windows from {start}: only {secondary_count} secondary windows"
+                );
+            }
+        }
+
+        if self.config.clamp_scroll && !self.config.wrap {
+            self.config.scroll_offset = self
+                .config
+                .scroll_offset
+                .clamp(0.0, self.end_scroll_offset(view_count));
+        }
+
+        let padding = self.config.outer_padding;
+        if usable_width as i32 <= padding.horizontal() || usable_height as i32 <= padding.vertical()
+        {
+            warn!(
+                "usable area {usable_width}x{usable_height} leaves no room inside the outer padding of {padding:?}, clamping views to zero size"
+            );
+        }
+
+        if self.overview {
+            return Ok(self.generate_overview(view_count, usable_width, usable_height));
+        }
+
+        if self.config.thumbnail_rail {
+            return Ok(self.generate_rail(view_count, usable_width, usable_height));
+        }
+
+        if let Some(corner) = &self.config.main_corner {
+            return Ok(self.generate_corner_layout(
+                corner,
+                view_count,
+                usable_width,
+                usable_height,
+            ));
+        }
+
+        let padded_width = usable_width as i32 - padding.horizontal();
+        let padded_height = usable_height as i32 - padding.vertical();
+
+        // Negative padding only overlaps secondary windows with each other,
+        // never with the main area.
+        let split_padding = self.config.view_padding.max(0);
+
+        let fixed_width = self.config.main_fixed_size.map(|size| size.x);
+        let fixed_height = self.config.main_fixed_size.map(|size| size.y);
+
+        let main_split = |padded_size: i32, ratio: f32, fixed_size: Option<i32>| {
+            if self.main_minimized {
+                self.config
+                    .dock_size
+                    .min(padded_size - split_padding)
+                    .max(0)
+            } else if let Some(size) = fixed_size {
+                size.min(padded_size - split_padding).max(0)
+            } else {
+                ((padded_size - split_padding) as f32 * ratio) as i32
+            }
+        };
+        let main_split_widthwise = main_split(padded_width, self.config.main_ratio, fixed_width);
+        let main_split_heightwise = main_split(padded_height, self.config.main_ratio, fixed_height);
+
+        // When decoupled from the main ratio, the secondary area keeps the size
+        // it has at its own ratio, overflowing the output if the main area
+        // grows into it.
+        let secondary_split = |padded_size: i32, main_split_size: i32, fixed_size| {
+            let main_split_size = self.secondary_ratio.map_or(main_split_size, |ratio| {
+                main_split(padded_size, ratio, fixed_size)
+            });
+            padded_size - split_padding - main_split_size
+        };
+        let secondary_split_widthwise =
+            secondary_split(padded_width, main_split_widthwise, fixed_width);
+        let secondary_split_heightwise =
+            secondary_split(padded_height, main_split_heightwise, fixed_height);
+
+        let main_split_area = match self.config.main_location {
+            Edge::Left => Rectangle {
+                x: padding.left,
+                y: padding.top,
+                width: dimension(main_split_widthwise),
+                height: dimension(padded_height),
+            },
+            Edge::Top => Rectangle {
+                x: padding.left,
+                y: padding.top,
+                width: dimension(padded_width),
+                height: dimension(main_split_heightwise),
+            },
+            Edge::Right => Rectangle {
+                x: usable_width as i32 - padding.right - main_split_widthwise,
+                y: padding.top,
+                width: dimension(main_split_widthwise),
+                height: dimension(padded_height),
+            },
+            Edge::Bottom => Rectangle {
+                x: padding.left,
+                y: usable_height as i32 - padding.bottom - main_split_heightwise,
+                width: dimension(padded_width),
+                height: dimension(main_split_heightwise),
+            },
+        };
+
+        // The main area is centered within its split wherever it doesn't fill
+        // it, however unevenly the ratio and paddings divide: along its edge
+        // for a fixed size, and along either dimension for `main_aspect`.
+        let span = |size: u32, fixed_size: Option<i32>| {
+            fixed_size.map_or(size, |fixed_size| dimension(fixed_size).min(size))
+        };
+        let main_area = match self.config.main_location {
+            Edge::Left | Edge::Right => center(
+                &main_split_area,
+                main_split_area.width,
+                span(main_split_area.height, fixed_height),
+            ),
+            Edge::Top | Edge::Bottom => center(
+                &main_split_area,
+                span(main_split_area.width, fixed_width),
+                main_split_area.height,
+            ),
+        };
+        let main_area = match self.config.main_aspect {
+            Some(aspect) => letterbox(&main_area, aspect),
+            None => main_area,
+        };
+
+        let secondary_area = match self.config.main_location {
+            Edge::Left => Rectangle {
+                x: padding.left + main_split_widthwise + split_padding,
+                y: padding.top,
+                width: dimension(secondary_split_widthwise),
+                height: dimension(padded_height),
+            },
+            Edge::Top => Rectangle {
+                x: padding.left,
+                y: padding.top + main_split_heightwise + split_padding,
+                width: dimension(padded_width),
+                height: dimension(secondary_split_heightwise),
+            },
+            Edge::Right => Rectangle {
+                x: usable_width as i32
+                    - padding.right
+                    - main_split_widthwise
+                    - split_padding
+                    - secondary_split_widthwise,
+                y: padding.top,
+                width: dimension(secondary_split_widthwise),
+                height: dimension(padded_height),
+            },
+            Edge::Bottom => Rectangle {
+                x: padding.left,
+                y: usable_height as i32
+                    - padding.bottom
+                    - main_split_heightwise
+                    - split_padding
+                    - secondary_split_heightwise,
+                width: dimension(padded_width),
+                height: dimension(secondary_split_heightwise),
+            },
+        };
+
+        let scroll_axis = match (
+            &self.config.scroll_axis,
+            &self.config.main_location,
+            self.config.stacked,
+        ) {
+            (Some(Axis::Vertical), _, _)
+            | (None, Edge::Left | Edge::Right, false)
+            | (None, Edge::Top | Edge::Bottom, true) => Axis::Vertical,
+            (Some(Axis::Horizontal), _, _)
+            | (None, Edge::Left | Edge::Right, true)
+            | (None, Edge::Top | Edge::Bottom, false) => Axis::Horizontal,
+        };
+        let main_count = self.main_count(view_count);
+        let main_views = split(&main_area, &scroll_axis, main_count, split_padding);
+        // Rotating the strip leaves the areas in place, only browsing the
+        // secondary windows along the other axis.
+        let scroll_axis = match (scroll_axis, self.strip_rotated) {
+            (Axis::Horizontal, true) => Axis::Vertical,
+            (Axis::Vertical, true) => Axis::Horizontal,
+            (axis, false) => axis,
+        };
+
+        let secondary_count = view_count.saturating_sub(main_count) as i32;
+        let secondary_size = |area_size: u32| {
+            let (size, _) = self.secondary_window_length(area_size);
+            if size < self.config.min_view_size {
+                warn!(
+                    "secondary area of {area_size} pixels is smaller than the minimum view size of {}",
+                    self.config.min_view_size
+                );
+            }
+
+            // A loop has to reach across the whole secondary area besides the
+            // window straddling its seam, or it leaves a gap there.
+            match secondary_count {
+                _ if !self.config.wrap => size,
+                0 | 1 => area_size as i32,
+                count => {
+                    let stride = ((area_size as i32 + self.config.view_padding) as f32
+                        / (count - 1) as f32)
+                        .ceil() as i32;
+                    size.max(stride - self.config.view_padding)
+                }
+            }
+        };
+        // Only a secondary area scrolling away from the main area needs to
+        // run backwards to start next to it.
+        let mirrored = self.config.secondary_from_main
+            && matches!(
+                (&scroll_axis, &self.config.main_location),
+                (Axis::Horizontal, Edge::Right) | (Axis::Vertical, Edge::Bottom)
+            );
+        let direction = if mirrored { -1 } else { 1 };
+
+        let (secondary_size, secondary_base, secondary_stride_x, secondary_stride_y) =
+            match scroll_axis {
+                Axis::Horizontal => {
+                    let size = secondary_size(secondary_area.width);
+                    let base = Rectangle {
+                        x: if mirrored {
+                            secondary_area.x + secondary_area.width as i32 - size
+                        } else {
+                            secondary_area.x
+                        },
+                        width: dimension(size),
+                        ..secondary_area
+                    };
+                    (size, base, direction * (size + self.config.view_padding), 0)
+                }
+                Axis::Vertical => {
+                    let size = secondary_size(secondary_area.height);
+                    let base = Rectangle {
+                        y: if mirrored {
+                            secondary_area.y + secondary_area.height as i32 - size
+                        } else {
+                            secondary_area.y
+                        },
+                        height: dimension(size),
+                        ..secondary_area
+                    };
+                    (size, base, 0, direction * (size + self.config.view_padding))
+                }
+            };
+        self.secondary_area_size = Some(match scroll_axis {
+            Axis::Horizontal => secondary_area.width,
+            Axis::Vertical => secondary_area.height,
+        });
+
+        if let Some(min_size) = self.config.stack_when_crowded {
+            if secondary_size < min_size {
+                return Ok(GeneratedLayout {
+                    layout_name: self
+                        .layout_name(self.config.main_location, self.config.scroll_offset),
+                    views: main_views
+                        .into_iter()
+                        .chain(stack(
+                            secondary_area,
+                            view_count - main_count,
+                            self.config.scroll_offset,
+                        ))
+                        .take(view_count as usize)
+                        .collect(),
+                });
+            }
+        }
+
+        let secondary_views: Box<dyn Iterator<Item = Rectangle>> = if self.config.wrap {
+            Box::new(wrapped_strip(
+                secondary_base,
+                secondary_stride_x,
+                secondary_stride_y,
+                self.config.scroll_offset,
+                view_count - main_count,
+            ))
+        } else {
+            Box::new(strip(
+                secondary_base,
+                secondary_stride_x,
+                secondary_stride_y,
+                self.config.scroll_offset,
+            ))
+        };
+
+        let (focus_center, focus_radius) = match scroll_axis {
+            Axis::Horizontal => (
+                secondary_area.x as f32 + secondary_area.width as f32 / 2.0,
+                secondary_area.width as f32 / 2.0,
+            ),
+            Axis::Vertical => (
+                secondary_area.y as f32 + secondary_area.height as f32 / 2.0,
+                secondary_area.height as f32 / 2.0,
+            ),
+        };
+        let secondary_views = secondary_views.map(|view| {
+            let view = match self.config.secondary_aspect {
+                Some(aspect) => letterbox(&view, aspect),
+                None => view,
+            };
+            if self.config.focus_scale == 1.0 {
+                return view;
+            }
+            let view_center = match scroll_axis {
+                Axis::Horizontal => view.x as f32 + view.width as f32 / 2.0,
+                Axis::Vertical => view.y as f32 + view.height as f32 / 2.0,
+            };
+            let distance = ((view_center - focus_center).abs() / focus_radius.max(1.0)).min(1.0);
+            scale(&view, 1.0 + (self.config.focus_scale - 1.0) * distance)
+        });
+
+        Ok(GeneratedLayout {
+            layout_name: self.layout_name(self.config.main_location, self.config.scroll_offset),
+            views: main_views
+                .into_iter()
+                .chain(secondary_views)
+                .take(view_count as usize)
+                .collect(),
+        })
+    }
+
+    /// Applies the adjustments made to every generated layout.
+    fn finish_layout(
+        &self,
+        mut layout: GeneratedLayout,
+        usable_width: u32,
+        usable_height: u32,
+    ) -> GeneratedLayout {
+        if self.config.hide_offscreen {
+            // The main area can't be scrolled away.
+            let main_count = self.main_count(layout.views.len() as u32) as usize;
+            for view in layout.views.iter_mut().skip(main_count) {
+                *view = collapse_offscreen(view, usable_width, usable_height);
+            }
+        }
+
+        // A promoted view takes the main area even from `main_tag`, as long
+        // as it is still there.
+        let promoted = self
+            .promoted
+            .map(|index| index as usize)
+            .filter(|&index| index < layout.views.len());
+        let tagged = self.config.main_tag.and_then(|main_tag| {
+            (0..layout.views.len()).find(|index| {
+                self.view_tags
+                    .get(&(*index as u32))
+                    .is_some_and(|tags| tags & main_tag != 0)
+            })
+        });
+        if let Some(main_index) = promoted.or(tagged) {
+            // Move the main area to the matching view and the ones after it,
+            // as many as it holds, shifting the views before them into the
+            // secondary area.
+            let main_count = self.main_count(layout.views.len() as u32) as usize;
+            let main_index = main_index.min(layout.views.len() - main_count);
+            let main_areas: Vec<_> = layout.views.drain(..main_count).collect();
+            layout.views.splice(main_index..main_index, main_areas);
+        }
+
+        for view in &mut layout.views {
+            view.x = view.x.saturating_add(self.safe_area.left as i32);
+            view.y = view.y.saturating_add(self.safe_area.top as i32);
+        }
+
+        if self.config.respect_client_aspect {
+            for (&index, &aspect) in &self.view_aspects {
+                if let Some(view) = layout.views.get_mut(index as usize) {
+                    *view = letterbox(view, aspect);
+                }
+            }
+        }
+
+        layout
+    }
+
+    /// Lays out the main area above a rail of thumbnails, for
+    /// `Config::thumbnail_rail`.
+    fn generate_rail(
+        &self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+    ) -> GeneratedLayout {
+        let padding = self.config.outer_padding;
+        let padded_width = usable_width as i32 - padding.horizontal();
+        let padded_height = usable_height as i32 - padding.vertical();
+        let split_padding = self.config.view_padding.max(0);
+
+        let rail_height = self
+            .config
+            .rail_height
+            .min(padded_height - split_padding)
+            .max(0);
+        let main_height = padded_height - split_padding - rail_height;
+        let main_area = Rectangle {
+            x: padding.left,
+            y: padding.top,
+            width: dimension(padded_width),
+            height: dimension(main_height),
+        };
+        let main_count = self.main_count(view_count);
+        let main_views = split(&main_area, &Axis::Horizontal, main_count, split_padding);
+
+        let thumbnail_width = ((padded_width + self.config.view_padding) as f32
+            * self.config.secondary_window_size) as i32
+            - self.config.view_padding;
+        let base = Rectangle {
+            x: padding.left,
+            y: padding.top + main_height + split_padding,
+            width: dimension(thumbnail_width),
+            height: dimension(rail_height),
+        };
+        let stride = thumbnail_width + self.config.view_padding;
+        let thumbnails: Box<dyn Iterator<Item = Rectangle>> = if self.config.wrap {
+            Box::new(wrapped_strip(
+                base,
+                stride,
+                0,
+                self.config.scroll_offset,
+                view_count - main_count,
+            ))
+        } else {
+            Box::new(strip(base, stride, 0, self.config.scroll_offset))
+        };
+
+        GeneratedLayout {
+            layout_name: format!("{}: rail", Self::NAMESPACE),
+            views: main_views
+                .into_iter()
+                .chain(thumbnails)
+                .take(view_count as usize)
+                .collect(),
+        }
+    }
+
+    /// Lays out every view, including the main one, in a grid filling the
+    /// layout area.
+    ///
+    /// The grid is as close to square as possible, with any empty cells at
+    /// the end of the last row.
+    fn generate_overview(
+        &self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+    ) -> GeneratedLayout {
+        let padding = self.config.outer_padding;
+        let padded_width = usable_width as i32 - padding.horizontal();
+        let padded_height = usable_height as i32 - padding.vertical();
+
+        let columns = (view_count as f32).sqrt().ceil().max(1.0) as i32;
+        let rows = (view_count as i32 + columns - 1) / columns;
+
+        let stride_x = (padded_width + self.config.view_padding) / columns;
+        let stride_y = (padded_height + self.config.view_padding) / rows.max(1);
+
+        GeneratedLayout {
+            layout_name: format!("{}: overview", Self::NAMESPACE),
+            views: (0..view_count as i32)
+                .map(|i| Rectangle {
+                    x: padding.left + stride_x * (i % columns),
+                    y: padding.top + stride_y * (i / columns),
+                    width: dimension(stride_x - self.config.view_padding),
+                    height: dimension(stride_y - self.config.view_padding),
+                })
+                .collect(),
+        }
+    }
+
+    /// Name of a layout with the main area at the given position, scrolled to
+    /// the given offset, such as `carousel: left @2.3`.
+    ///
+    /// The name always starts with the namespace, for filtering.
+    fn layout_name(&self, position: impl fmt::Display, scroll_offset: f32) -> String {
+        format!("{}: {position} @{scroll_offset:.1}", Self::NAMESPACE)
+    }
+
+    fn generate_corner_layout(
+        &self,
+        corner: &Corner,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+    ) -> GeneratedLayout {
+        let padding = self.config.outer_padding;
+        let padded_width = usable_width as i32 - padding.horizontal();
+        let padded_height = usable_height as i32 - padding.vertical();
+
+        // Negative padding only overlaps secondary windows with each other,
+        // never with the main area.
+        let split_padding = self.config.view_padding.max(0);
+
+        let main_width = ((padded_width - split_padding) as f32 * self.config.main_ratio) as i32;
+        let main_height = ((padded_height - split_padding) as f32 * self.config.main_ratio) as i32;
+
+        let strip_width = padded_width - split_padding - main_width;
+        let strip_height = padded_height - split_padding - main_height;
+
+        let left = matches!(corner, Corner::TopLeft | Corner::BottomLeft);
+        let top = matches!(corner, Corner::TopLeft | Corner::TopRight);
+
+        let main_x = if left {
+            padding.left
+        } else {
+            usable_width as i32 - padding.right - main_width
+        };
+        let main_y = if top {
+            padding.top
+        } else {
+            usable_height as i32 - padding.bottom - main_height
+        };
+
+        let main_area = Rectangle {
+            x: main_x,
+            y: main_y,
+            width: dimension(main_width),
+            height: dimension(main_height),
+        };
+
+        let horizontal_size = ((strip_width + self.config.view_padding) as f32
+            * self.config.secondary_window_size) as i32
+            - self.config.view_padding;
+        let vertical_size = ((strip_height + self.config.view_padding) as f32
+            * self.config.corner_window_size) as i32
+            - self.config.view_padding;
+
+        let horizontal_base = Rectangle {
+            x: if left {
+                padding.left + main_width + split_padding
+            } else {
+                padding.left
+            },
+            y: main_y,
+            width: dimension(horizontal_size),
+            height: dimension(main_height),
+        };
+        let vertical_base = Rectangle {
+            x: main_x,
+            y: if top {
+                padding.top + main_height + split_padding
+            } else {
+                padding.top
+            },
+            width: dimension(main_width),
+            height: dimension(vertical_size),
+        };
+
+        // Views scrolled or running into the main area stop at its edge
+        // instead, so the strips never cover it.
+        let horizontal_limit = main_x - split_padding - horizontal_size;
+        let vertical_limit = main_y - split_padding - vertical_size;
+        let horizontal_views = strip(
+            horizontal_base,
+            horizontal_size + self.config.view_padding,
+            0,
+            self.config.scroll_offset,
+        )
+        .map(|view| Rectangle {
+            x: if left {
+                view.x.max(horizontal_base.x)
+            } else {
+                view.x.min(horizontal_limit)
+            },
+            ..view
+        });
+        let vertical_views = strip(
+            vertical_base,
+            0,
+            vertical_size + self.config.view_padding,
+            self.config.corner_scroll_offset,
+        )
+        .map(|view| Rectangle {
+            y: if top {
+                view.y.max(vertical_base.y)
+            } else {
+                view.y.min(vertical_limit)
+            },
+            ..view
+        });
+
+        let secondary_count = view_count.saturating_sub(1) as usize;
+        let horizontal_count = secondary_count.div_ceil(2);
+
+        GeneratedLayout {
+            layout_name: self.layout_name(corner, self.config.scroll_offset),
+            views: [main_area]
+                .into_iter()
+                .chain(horizontal_views.take(horizontal_count))
+                .chain(vertical_views)
+                .take(view_count as usize)
+                .collect(),
+        }
+    }
+}
+
+/// Generates views along a scrolling strip, starting from `base` and stepping
+/// by `stride_x`/`stride_y` for each view.
+///
+/// `scroll_offset` shifts the whole strip backwards, in "number of windows".
+fn strip(
+    base: Rectangle,
+    stride_x: i32,
+    stride_y: i32,
+    scroll_offset: f32,
+) -> impl Iterator<Item = Rectangle> {
+    let scroll_x = (stride_x as f32 * scroll_offset) as i32;
+    let scroll_y = (stride_y as f32 * scroll_offset) as i32;
+
+    (0i32..).map(move |i| Rectangle {
+        x: base
+            .x
+            .saturating_add(stride_x.saturating_mul(i))
+            .saturating_sub(scroll_x),
+        y: base
+            .y
+            .saturating_add(stride_y.saturating_mul(i))
+            .saturating_sub(scroll_y),
+        width: base.width,
+        height: base.height,
+    })
+}
+
+/// Generates a loop of `count` views along a scrolling strip, like `strip`,
+/// where views scrolled past either end come back around at the other.
+///
+/// Scrolling by `count` brings every view back to where it started. A view can
+/// only be in one place, so while one straddles the seam between the end and
+/// the start of the loop, it is shown at the start, and the loop has to be
+/// long enough for the views after it to fill the rest of the strip. A single
+/// view has nowhere to go around to, and stays at the start.
+fn wrapped_strip(
+    base: Rectangle,
+    stride_x: i32,
+    stride_y: i32,
+    scroll_offset: f32,
+    count: u32,
+) -> impl Iterator<Item = Rectangle> {
+    let loop_length = count as f32;
+
+    (0..count).map(move |i| {
+        let position = match (i as f32 - scroll_offset).rem_euclid(loop_length) {
+            _ if count == 1 => 0.0,
+            position if position > loop_length - 1.0 => position - loop_length,
+            position => position,
+        };
+
+        // Rounding down either side of the start keeps the views a whole
+        // stride apart.
+        Rectangle {
+            x: base
+                .x
+                .saturating_add((stride_x as f32 * position).floor() as i32),
+            y: base
+                .y
+                .saturating_add((stride_y as f32 * position).floor() as i32),
+            width: base.width,
+            height: base.height,
+        }
+    })
+}
+
+/// Generates `count` views stacked in the same `area`, where only the one
+/// selected by `scroll_offset` is shown and the rest are collapsed.
+fn stack(area: Rectangle, count: u32, scroll_offset: f32) -> impl Iterator<Item = Rectangle> {
+    let selected = (scroll_offset.round() as i64).rem_euclid(count.max(1) as i64) as u32;
+
+    (0..count).map(move |i| {
+        let shown = i == selected;
+        Rectangle {
+            x: area.x,
+            y: area.y,
+            width: if shown { area.width } else { 0 },
+            height: if shown { area.height } else { 0 },
+        }
+    })
+}
+
+/// Converts a computed size into a view dimension.
+///
+/// Sizes go negative when the layout area is too small for its padding, in
+/// which case the view collapses to zero instead.
+fn dimension(size: i32) -> u32 {
+    size.max(0) as u32
+}
+
+/// Parses an aspect ratio, either as `<width>:<height>` or as a single number.
+fn parse_aspect(arg: &str) -> Option<f32> {
+    let aspect = match arg.split_once(':') {
+        Some((width, height)) => width.parse::<f32>().ok()? / height.parse::<f32>().ok()?,
+        None => arg.parse().ok()?,
+    };
+
+    (aspect.is_finite() && aspect > 0.0).then_some(aspect)
+}
+
+/// Shrinks `rect` to the given aspect ratio, centered within the original.
+fn letterbox(rect: &Rectangle, aspect: f32) -> Rectangle {
+    let (width, height) = if rect.width as f32 > rect.height as f32 * aspect {
+        ((rect.height as f32 * aspect) as u32, rect.height)
+    } else {
+        (rect.width, (rect.width as f32 / aspect) as u32)
+    };
+
+    center(rect, width, height)
+}
+
+/// A rectangle of the given size, centered within `rect` along each dimension
+/// it fits in.
+///
+/// The space left on either side differs by at most the one odd pixel, which
+/// goes after the new rectangle. Along a dimension that doesn't fit, it starts
+/// at the start of `rect` instead.
+fn center(rect: &Rectangle, width: u32, height: u32) -> Rectangle {
+    Rectangle {
+        x: rect
+            .x
+            .saturating_add((rect.width.saturating_sub(width) / 2) as i32),
+        y: rect
+            .y
+            .saturating_add((rect.height.saturating_sub(height) / 2) as i32),
+        width,
+        height,
+    }
+}
+
+/// Divides `rect` evenly into `count` parts along `axis`, `padding` apart.
+fn split(rect: &Rectangle, axis: &Axis, count: u32, padding: i32) -> Vec<Rectangle> {
+    let count = count.max(1) as i32;
+    let length = match axis {
+        Axis::Horizontal => rect.width as i32,
+        Axis::Vertical => rect.height as i32,
+    };
+    let edge = |i: i32| ((length + padding) as i64 * i as i64 / count as i64) as i32;
+
+    (0..count)
+        .map(|i| {
+            let start = edge(i);
+            let size = dimension(edge(i + 1) - padding - start);
+            match axis {
+                Axis::Horizontal => Rectangle {
+                    x: rect.x + start,
+                    width: size,
+                    ..*rect
+                },
+                Axis::Vertical => Rectangle {
+                    y: rect.y + start,
+                    height: size,
+                    ..*rect
+                },
+            }
+        })
+        .collect()
+}
+
+/// Scales `rect` by the given factor, centered within the original.
+fn scale(rect: &Rectangle, factor: f32) -> Rectangle {
+    let factor = factor.max(0.0);
+    let width = (rect.width as f32 * factor) as u32;
+    let height = (rect.height as f32 * factor) as u32;
+
+    center(rect, width, height)
+}
+
+/// Collapses `rect` to zero size at the nearest point of the area of the
+/// given size, if it lies entirely outside of it.
+fn collapse_offscreen(rect: &Rectangle, width: u32, height: u32) -> Rectangle {
+    let signed = |size: u32| i32::try_from(size).unwrap_or(i32::MAX);
+    let (width, height) = (signed(width), signed(height));
+    let right = rect.x.saturating_add(signed(rect.width));
+    let bottom = rect.y.saturating_add(signed(rect.height));
+    if rect.x < width && rect.y < height && right > 0 && bottom > 0 {
+        return *rect;
+    }
+
+    Rectangle {
+        x: rect.x.clamp(0, width),
+        y: rect.y.clamp(0, height),
+        width: 0,
+        height: 0,
+    }
+}
+
+impl Layout for Carousel {
+    type Error = Error;
+
+    const NAMESPACE: &'static str = "carousel";
+
+    fn user_cmd(
+        &mut self,
+        cmd: String,
+        tags: Option<u32>,
+        output: &str,
+    ) -> Result<(), Self::Error> {
+        self.stats.commands_received += 1;
+        self.cache = None;
+        if let Some(tags) = tags {
+            self.activate_tags(tags);
+        }
+        let scroll_offsets = (self.config.scroll_offset, self.config.corner_scroll_offset);
+        let result = self.user_cmd_inner(cmd, tags, output);
+        if let Err(e) = &result {
+            error!("{e}");
+        }
+        // Scrolling displaces a promoted view, unless it is pinned.
+        if !self.config.pin_main
+            && (self.config.scroll_offset, self.config.corner_scroll_offset) != scroll_offsets
+        {
+            self.promoted = None;
+        }
+
+        result
+    }
+
+    fn generate_layout(
+        &mut self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+        output: &str,
+    ) -> Result<GeneratedLayout, Self::Error> {
+        if view_count > MAX_VIEW_COUNT {
+            return Err(Error::TooManyViews(view_count));
+        }
+        let start = Instant::now();
+        self.activate_tags(tags);
+        let safe_width = usable_width
+            .saturating_sub(self.safe_area.left.saturating_add(self.safe_area.right))
+            .min(MAX_USABLE_SIZE);
+        let safe_height = usable_height
+            .saturating_sub(self.safe_area.top.saturating_add(self.safe_area.bottom))
+            .min(MAX_USABLE_SIZE);
+        let cached = self.cached(view_count, usable_width, usable_height, tags, output);
+        // Paddings are only scaled for the duration of this layout.
+        let unscaled_padding = (self.config.outer_padding, self.config.view_padding);
+        if self.config.logical_padding || self.config.gaps_scale_with_output {
+            let scale = if self.config.gaps_scale_with_output {
+                let size = usable_width.min(usable_height).min(MAX_USABLE_SIZE);
+                size as f32 / GAP_FRACTION_PARTS
+            } else {
+                self.output_scales.get(output).copied().unwrap_or(1.0)
+            };
+            // Scaled paddings are bounded like the ones set by commands.
+            let scaled = |padding: i32| {
+                ((padding as f32 * scale).round() as i32).clamp(i16::MIN.into(), i16::MAX.into())
+            };
+            self.config.outer_padding = self.config.outer_padding.map(scaled);
+            self.config.view_padding = scaled(self.config.view_padding);
+        }
+        let result = match (cached, &self.frozen) {
+            (Some(layout), _) => Ok(layout),
+            // Views added while frozen are hidden until unfrozen.
+            (None, Some(frozen)) => Ok(GeneratedLayout {
+                layout_name: format!("{}: frozen", Self::NAMESPACE),
+                views: frozen
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::repeat(Rectangle {
+                        x: 0,
+                        y: 0,
+                        width: 0,
+                        height: 0,
+                    }))
+                    .take(view_count as usize)
+                    .collect(),
+            }),
+            (None, None) => self
+                .generate_layout_inner(view_count, safe_width, safe_height, tags, output)
+                .map(|layout| self.finish_layout(layout, safe_width, safe_height)),
+        };
+        (self.config.outer_padding, self.config.view_padding) = unscaled_padding;
+        if let Ok(layout) = &result {
+            debug!("{}: {:?}", layout.layout_name, layout.views);
+            self.last_layout = Some(GeneratedLayout {
+                layout_name: layout.layout_name.clone(),
+                views: layout.views.clone(),
+            });
+            self.cache = Some(LayoutInputs {
+                view_count,
+                usable_width,
+                usable_height,
+                tags,
+                output: output.into(),
+            });
+        }
+        self.stats.layouts_generated += 1;
+        self.stats.last_generation_time = start.elapsed();
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -1,8 +1,93 @@
-use river_carousel_layout::Carousel;
+#[path = "../../shared/once.rs"]
+mod once;
+
+#[cfg(feature = "serde")]
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+#[cfg(feature = "serde")]
+use river_carousel_layout::ConfigBuilder;
+use river_carousel_layout::{Carousel, Config, Edge};
+
+#[derive(Parser)]
+struct Args {
+    /// Generate a single layout and print it as JSON, instead of running as a
+    /// layout generator.
+    ///
+    /// The layout parameters are read from standard input, as
+    /// `<view_count> <width> <height> <tags> <output>`.
+    #[arg(long)]
+    once: bool,
+
+    /// TOML file to load the configuration from, before applying the rest of
+    /// the arguments on top of it.
+    #[cfg(feature = "serde")]
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Edge that the main area extends from: left, right, top, or bottom.
+    #[arg(long)]
+    main_location: Option<Edge>,
+
+    /// Fraction of the layout area taken up by the main area.
+    #[arg(long)]
+    main_ratio: Option<f32>,
+
+    /// Fraction of the secondary area taken up by each secondary window.
+    #[arg(long)]
+    secondary_size: Option<f32>,
+
+    /// Padding around the edge of the layout area, in pixels.
+    #[arg(long)]
+    outer_padding: Option<i32>,
+
+    /// Padding between views, in pixels. May be negative to overlap the
+    /// secondary windows.
+    #[arg(long, allow_hyphen_values = true)]
+    view_padding: Option<i32>,
+}
+
+impl Args {
+    /// The configuration given by the arguments, on top of the one from the
+    /// config file, or the default one.
+    fn config(&self) -> anyhow::Result<Config> {
+        #[cfg(feature = "serde")]
+        let mut config = match &self.config {
+            Some(path) => ConfigBuilder::from(Config::from_file(path)?),
+            None => Config::builder(),
+        };
+        #[cfg(not(feature = "serde"))]
+        let mut config = Config::builder();
+        if let Some(main_location) = self.main_location {
+            config = config.main_location(main_location);
+        }
+        if let Some(main_ratio) = self.main_ratio {
+            config = config.main_ratio(main_ratio);
+        }
+        if let Some(secondary_size) = self.secondary_size {
+            config = config.secondary_window_size(secondary_size);
+        }
+        if let Some(outer_padding) = self.outer_padding {
+            config = config.outer_padding(outer_padding);
+        }
+        if let Some(view_padding) = self.view_padding {
+            config = config.view_padding(view_padding);
+        }
+
+        config.build().context("invalid configuration")
+    }
+}
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
+    let args = Args::parse();
+
+    let layout = Carousel::new(args.config()?);
+    if args.once {
+        return once::once(layout);
+    }
 
-    river_layout_toolkit::run(Carousel::new(Default::default()))?;
+    river_layout_toolkit::run(layout)?;
     Ok(())
 }
@@ -0,0 +1,2302 @@
+use river_layout_toolkit::{GeneratedLayout, Layout, Rectangle};
+
+use super::*;
+
+fn layout(carousel: &mut Carousel, view_count: u32, width: u32, height: u32) -> GeneratedLayout {
+    carousel
+        .generate_layout(view_count, width, height, 1, "test")
+        .unwrap()
+}
+
+fn cmd(carousel: &mut Carousel, cmd: &str) {
+    carousel.user_cmd(cmd.into(), None, "test").unwrap();
+}
+
+/// Views as `(x, y, width, height)`, to compare them whole.
+fn rects(views: &[Rectangle]) -> Vec<(i32, i32, u32, u32)> {
+    views
+        .iter()
+        .map(|view| (view.x, view.y, view.width, view.height))
+        .collect()
+}
+
+/// Output sizes to check layouts across: landscape, portrait, and odd.
+const OUTPUT_SIZES: [(u32, u32); 4] = [(1920, 1080), (1080, 1920), (1366, 768), (801, 601)];
+
+/// Asserts river's coordinate conventions: every view lies within the usable
+/// area, whose origin is its top-left corner.
+fn assert_within(views: &[Rectangle], width: u32, height: u32) {
+    for view in views {
+        assert!(view.x >= 0 && view.y >= 0, "{view:?}");
+        assert!(
+            view.x as u32 + view.width <= width && view.y as u32 + view.height <= height,
+            "{view:?} past {width}x{height}",
+        );
+    }
+}
+
+#[test]
+fn corner_strips_never_cover_the_main_area() {
+    let overlaps = |a: &Rectangle, b: &Rectangle| {
+        a.x < b.x + b.width as i32
+            && b.x < a.x + a.width as i32
+            && a.y < b.y + b.height as i32
+            && b.y < a.y + a.height as i32
+    };
+    let corners = [
+        || Corner::TopLeft,
+        || Corner::TopRight,
+        || Corner::BottomLeft,
+        || Corner::BottomRight,
+    ];
+    for corner in corners {
+        for (width, height) in OUTPUT_SIZES {
+            for offset in [-3.0, -1.0, -0.5, 0.0, 1.0, 2.5, 5.0] {
+                let mut carousel = Carousel::new(Config {
+                    main_corner: Some(corner()),
+                    scroll_offset: offset,
+                    corner_scroll_offset: -offset,
+                    ..Config::default()
+                });
+                let views = layout(&mut carousel, 9, width, height).views;
+                let main = &views[0];
+                for view in &views[1..] {
+                    assert!(
+                        !overlaps(main, view),
+                        "{view:?} covers {main:?} at {offset} on {width}x{height}",
+                    );
+                }
+            }
+        }
+    }
+
+    // Scrolling the horizontal strip back by one window in a left corner
+    // keeps the first secondary window beside the main area.
+    let mut carousel = Carousel::new(Config {
+        main_corner: Some(Corner::TopLeft),
+        ..Config::default()
+    });
+    cmd(&mut carousel, "scroll 1");
+    let views = layout(&mut carousel, 5, 1920, 1080).views;
+    assert_eq!(rects(&views[..2]), [(6, 6, 1141, 637), (1153, 6, 377, 637)]);
+}
+
+#[test]
+fn corner_mode_places_the_main_area_and_both_strips() {
+    let mut carousel = Carousel::new(Config {
+        main_corner: Some(Corner::TopLeft),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 7, 1920, 1080).views),
+        [
+            (6, 6, 1141, 637),
+            (1153, 6, 377, 637),
+            (1536, 6, 377, 637),
+            (1919, 6, 377, 637),
+            (6, 649, 1141, 209),
+            (6, 864, 1141, 209),
+            (6, 1079, 1141, 209),
+        ],
+    );
+
+    // Each strip scrolls on its own.
+    cmd(&mut carousel, "scroll -1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 7, 1920, 1080).views[1..4]),
+        [
+            (1536, 6, 377, 637),
+            (1919, 6, 377, 637),
+            (2302, 6, 377, 637)
+        ],
+    );
+    cmd(&mut carousel, "corner-scroll -1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 7, 1920, 1080).views[1..]),
+        [
+            (1536, 6, 377, 637),
+            (1919, 6, 377, 637),
+            (2302, 6, 377, 637),
+            (6, 864, 1141, 209),
+            (6, 1079, 1141, 209),
+            (6, 1294, 1141, 209),
+        ],
+    );
+
+    let mut carousel = Carousel::new(Config {
+        main_corner: Some(Corner::BottomRight),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        [
+            (773, 437, 1141, 637),
+            (6, 437, 377, 637),
+            (389, 437, 377, 637),
+            (773, 6, 1141, 209),
+            (773, 221, 1141, 209),
+        ],
+    );
+}
+
+#[test]
+fn rivertile_preset_matches_rivertile_for_three_views() {
+    // rivertile's defaults on 1920x1080: 6 pixels of outer padding, then a
+    // main ratio of 0.6 of the remaining 1908 pixels, then 6 pixels of view
+    // padding on every side of every view.
+    let rivertile = [
+        (12, 12, 1132, 1056),
+        (1156, 12, 752, 522),
+        (1156, 546, 752, 522),
+    ];
+
+    let mut carousel = Carousel::new(Config::rivertile());
+    let views = rects(&layout(&mut carousel, 3, 1920, 1080).views);
+    for (view, expected) in views.iter().zip(rivertile) {
+        let (x, y, width, height) = *view;
+        assert!(
+            x.abs_diff(expected.0) <= 2
+                && y.abs_diff(expected.1) <= 2
+                && width.abs_diff(expected.2) <= 2
+                && height.abs_diff(expected.3) <= 2,
+            "{view:?} isn't within 2 pixels of {expected:?}",
+        );
+    }
+}
+
+#[test]
+fn negative_view_padding_overlaps_secondary_windows() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-view-padding -20");
+    let views = layout(&mut carousel, 4, 1920, 1080).views;
+    assert_eq!(
+        rects(&views),
+        [
+            (6, 6, 1144, 1068),
+            (1150, 6, 764, 544),
+            (1150, 530, 764, 544),
+            (1150, 1054, 764, 544),
+        ],
+    );
+
+    // Each secondary window overlaps the one before it by 20 pixels, and the
+    // ones in view stay within the output.
+    for pair in views[1..].windows(2) {
+        assert_eq!(pair[0].y + pair[0].height as i32 - pair[1].y, 20);
+    }
+    assert_within(&views[..3], 1920, 1080);
+}
+
+#[test]
+fn stats_count_layouts_and_commands() {
+    let mut carousel = Carousel::new(Config::default());
+    for view_count in [1, 2, 3, 3, 3] {
+        layout(&mut carousel, view_count, 1920, 1080);
+    }
+    cmd(&mut carousel, "stats");
+    assert!(carousel
+        .user_cmd("no-such-command".into(), None, "test")
+        .is_err());
+
+    assert_eq!(carousel.stats().layouts_generated, 5);
+    assert_eq!(carousel.stats().commands_received, 2);
+}
+
+#[test]
+fn crowded_secondary_windows_are_stacked() {
+    let mut carousel = Carousel::new(Config {
+        stack_when_crowded: Some(300),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views[1..]),
+        [
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+            (1153, 1080, 761, 531),
+        ],
+    );
+
+    // Windows of about 264 pixels would be smaller than the threshold.
+    let mut carousel = Carousel::new(Config {
+        secondary_window_size: 0.25,
+        stack_when_crowded: Some(300),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views[1..]),
+        [(1153, 6, 761, 1068), (1153, 6, 0, 0), (1153, 6, 0, 0)],
+    );
+    cmd(&mut carousel, "scroll 1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views[1..]),
+        [(1153, 6, 0, 0), (1153, 6, 761, 1068), (1153, 6, 0, 0)],
+    );
+}
+
+#[test]
+fn hinted_views_are_letterboxed_to_their_aspect() {
+    let mut carousel = Carousel::new(Config {
+        respect_client_aspect: true,
+        ..Config::default()
+    });
+    cmd(&mut carousel, "set-view-aspect 1 1:1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1268, 6, 531, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+
+    // Hints are ignored unless they are respected.
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-view-aspect 1 1:1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views)[1],
+        (1153, 6, 761, 531),
+    );
+}
+
+#[test]
+fn show_range_frames_exactly_the_given_windows() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "show-range 2 3");
+    let shown = [
+        (6, 6, 1141, 1068),
+        (1153, -710, 761, 352),
+        (1153, -352, 761, 352),
+        (1153, 6, 761, 352),
+        (1153, 364, 761, 352),
+        (1153, 722, 761, 352),
+        (1153, 1080, 761, 352),
+    ];
+    // Secondary windows 2, 3 and 4 fill the secondary area from top to bottom.
+    assert_eq!(rects(&layout(&mut carousel, 7, 1920, 1080).views), shown);
+
+    // A range past the last secondary window leaves the framing alone.
+    cmd(&mut carousel, "show-range 5 3");
+    assert_eq!(rects(&layout(&mut carousel, 7, 1920, 1080).views), shown);
+}
+
+#[test]
+fn negative_scroll_offsets_leave_space_before_the_first_window() {
+    let mut carousel = Carousel::new(Config {
+        scroll_offset: -0.5,
+        ..Config::default()
+    });
+    // Half of the 537-pixel stride is left empty above the first window.
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 274, 761, 531),
+            (1153, 811, 761, 531),
+            (1153, 1348, 761, 531),
+        ],
+    );
+
+    for invalid in [
+        "scroll NaN",
+        "scroll inf",
+        "corner-scroll -inf",
+        "set-scroll-step -inf",
+    ] {
+        assert!(matches!(
+            carousel.user_cmd(invalid.into(), None, "test"),
+            Err(Error::InvalidArgument(_)),
+        ));
+    }
+    assert_eq!(carousel.config.scroll_offset, -0.5);
+}
+
+#[test]
+fn minimized_main_area_shrinks_to_the_dock_size() {
+    let mut carousel = Carousel::new(Config::default());
+    let restored = rects(&layout(&mut carousel, 3, 1920, 1080).views);
+
+    // The secondary windows take up everything but the 24-pixel dock and
+    // the padding beside it.
+    cmd(&mut carousel, "minimize-main");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [(6, 6, 24, 1068), (36, 6, 1878, 531), (36, 543, 1878, 531),],
+    );
+
+    cmd(&mut carousel, "restore-main");
+    assert_eq!(rects(&layout(&mut carousel, 3, 1920, 1080).views), restored);
+}
+
+#[test]
+fn coordinates_increase_rightward_and_downward() {
+    let edges = [|| Edge::Left, || Edge::Right, || Edge::Top, || Edge::Bottom];
+    for edge in edges {
+        for (width, height) in OUTPUT_SIZES {
+            let mut carousel = Carousel::new(Config {
+                main_location: edge(),
+                ..Config::default()
+            });
+            for view_count in 1..=3 {
+                let views = layout(&mut carousel, view_count, width, height).views;
+                assert_within(&views, width, height);
+            }
+
+            let views = layout(&mut carousel, 3, width, height).views;
+            let (main, first, second) = (&views[0], &views[1], &views[2]);
+            match edge() {
+                Edge::Left => assert!(main.x < first.x),
+                Edge::Right => assert!(main.x > first.x),
+                Edge::Top => assert!(main.y < first.y),
+                Edge::Bottom => assert!(main.y > first.y),
+            }
+            // The secondary windows run along the main area, rightward or
+            // downward.
+            match edge() {
+                Edge::Left | Edge::Right => assert!(first.x == second.x && first.y < second.y),
+                Edge::Top | Edge::Bottom => assert!(first.y == second.y && first.x < second.x),
+            }
+        }
+    }
+}
+
+#[test]
+fn stacked_secondary_windows_continue_on_from_the_main_area() {
+    let mut carousel = Carousel::new(Config {
+        main_location: Edge::Top,
+        stacked: true,
+        ..Config::default()
+    });
+    // Full-width secondary windows run down from below the main area, two to
+    // the rest of the height.
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1908, 637),
+            (6, 649, 1908, 209),
+            (6, 864, 1908, 209),
+            (6, 1079, 1908, 209),
+        ],
+    );
+
+    // They scroll vertically, along the main split.
+    cmd(&mut carousel, "scroll -1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views[1..]),
+        [
+            (6, 864, 1908, 209),
+            (6, 1079, 1908, 209),
+            (6, 1294, 1908, 209),
+        ],
+    );
+}
+
+#[test]
+fn view_at_finds_the_view_under_a_point() {
+    let mut carousel = Carousel::new(Config::default());
+    assert_eq!(carousel.view_at(576, 540), None);
+
+    layout(&mut carousel, 3, 1920, 1080);
+    // The centers of the views.
+    assert_eq!(carousel.view_at(576, 540), Some(0));
+    assert_eq!(carousel.view_at(1533, 271), Some(1));
+    assert_eq!(carousel.view_at(1533, 808), Some(2));
+    // The padding between and around them.
+    assert_eq!(carousel.view_at(1150, 540), None);
+    assert_eq!(carousel.view_at(1533, 540), None);
+    assert_eq!(carousel.view_at(2, 2), None);
+    assert_eq!(carousel.view_at(-1, 540), None);
+}
+
+#[test]
+fn mod_main_ratio_only_keeps_secondary_window_sizes() {
+    let mut carousel = Carousel::new(Config::default());
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+
+    // The secondary windows move with the main area's edge, past the output
+    // or leaving empty space, but stay the same size.
+    cmd(&mut carousel, "mod-main-ratio-only 0.1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (6, 6, 1331, 1068),
+            (1343, 6, 761, 531),
+            (1343, 543, 761, 531),
+        ],
+    );
+    cmd(&mut carousel, "mod-main-ratio-only -0.3");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [(6, 6, 760, 1068), (772, 6, 761, 531), (772, 543, 761, 531),],
+    );
+
+    // Non-finite deltas are rejected, and the ratio stays within the range
+    // that stepping allows.
+    assert!(matches!(
+        carousel.user_cmd("mod-main-ratio-only NaN".into(), None, "test"),
+        Err(Error::InvalidArgument("delta")),
+    ));
+    cmd(&mut carousel, "mod-main-ratio-only 5");
+    assert_eq!(carousel.config.main_ratio, MAX_STEPPED_RATIO);
+
+    // Resizing the main area as usual sizes the secondary windows to fill
+    // the rest again.
+    cmd(&mut carousel, "set-main-ratio 0.4");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views)[1],
+        (772, 6, 1142, 531),
+    );
+}
+
+#[test]
+fn safe_area_insets_the_whole_layout() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-safe-area 40 10 20 30");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (36, 46, 1117, 1008),
+            (1159, 46, 745, 501),
+            (1159, 553, 745, 501),
+        ],
+    );
+
+    let edges = [|| Edge::Left, || Edge::Right, || Edge::Top, || Edge::Bottom];
+    for edge in edges {
+        let mut carousel = Carousel::new(Config {
+            main_location: edge(),
+            ..Config::default()
+        });
+        cmd(&mut carousel, "set-safe-area 40 10 20 30");
+        for (width, height) in OUTPUT_SIZES {
+            // Three views all fit without scrolling.
+            let mut views = layout(&mut carousel, 3, width, height).views;
+            for view in &mut views {
+                view.x -= 30;
+                view.y -= 40;
+            }
+            assert_within(&views, width - 40, height - 60);
+        }
+    }
+
+    assert!(matches!(
+        carousel.user_cmd("set-safe-area 1 2 3".into(), None, "test"),
+        Err(Error::MissingArgument("left")),
+    ));
+}
+
+#[test]
+fn scroll_axis_override_keeps_the_main_area_in_place() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-scroll-axis horizontal");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 377, 1068),
+            (1536, 6, 377, 1068),
+            (1919, 6, 377, 1068),
+        ],
+    );
+
+    // Scrolling moves the strip sideways, one window at a time.
+    cmd(&mut carousel, "scroll 1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (770, 6, 377, 1068),
+            (1153, 6, 377, 1068),
+            (1536, 6, 377, 1068),
+        ],
+    );
+
+    // Back to scrolling perpendicular to the main split.
+    cmd(&mut carousel, "set-scroll-axis auto");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+}
+
+#[test]
+fn frozen_layouts_ignore_new_views() {
+    let mut carousel = Carousel::new(Config::default());
+    assert!(matches!(
+        carousel.user_cmd("freeze".into(), None, "test"),
+        Err(Error::State {
+            operation: "freeze",
+            ..
+        }),
+    ));
+
+    let three = [
+        (6, 6, 1141, 1068),
+        (1153, 6, 761, 531),
+        (1153, 543, 761, 531),
+    ];
+    assert_eq!(rects(&layout(&mut carousel, 3, 1920, 1080).views), three);
+    cmd(&mut carousel, "freeze");
+
+    // New views are hidden, and closing views leaves the rest in place.
+    let frozen = layout(&mut carousel, 5, 1920, 1080);
+    assert_eq!(frozen.layout_name, "carousel: frozen");
+    assert_eq!(
+        rects(&frozen.views),
+        [three[0], three[1], three[2], (0, 0, 0, 0), (0, 0, 0, 0)],
+    );
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        three[..2]
+    );
+
+    cmd(&mut carousel, "unfreeze");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [three[0], three[1], three[2], (1153, 1080, 761, 531)],
+    );
+    assert!(matches!(
+        carousel.user_cmd("unfreeze".into(), None, "test"),
+        Err(Error::State {
+            operation: "unfreeze",
+            ..
+        }),
+    ));
+}
+
+#[test]
+fn main_tag_views_take_the_main_area() {
+    let mut carousel = Carousel::new(Config {
+        main_tag: Some(1 << 2),
+        ..Config::default()
+    });
+    let main = (6, 6, 1141, 1068);
+    let secondary = [(1153, 6, 761, 531), (1153, 543, 761, 531)];
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [main, secondary[0], secondary[1]],
+    );
+
+    cmd(&mut carousel, "set-view-tags 1 2");
+    cmd(&mut carousel, "set-view-tags 2 6");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [secondary[0], secondary[1], main],
+    );
+
+    // Without the matching view, the first view is in the main area again.
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [main, secondary[0]],
+    );
+}
+
+#[test]
+fn commands_are_split_on_any_whitespace() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, " \tset-main-ratio \t 0.4\n");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 760, 1068), (772, 6, 1142, 531)],
+    );
+
+    for command in [
+        "set-main-ratio\u{7}0.5",
+        "set-main-ratio 0.5\0",
+        "\u{1b}[Areset",
+    ] {
+        assert!(
+            matches!(
+                carousel.user_cmd(command.into(), None, "test"),
+                Err(Error::InvalidArgument("command")),
+            ),
+            "{command:?}",
+        );
+    }
+    assert_eq!(carousel.config.main_ratio, 0.4);
+    assert!(matches!(
+        carousel.user_cmd(" \t\n".into(), None, "test"),
+        Err(Error::UnknownCommand(_)),
+    ));
+}
+
+#[test]
+fn golden_ratio_toggles_between_the_golden_splits() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "golden-ratio");
+    assert!((carousel.config.main_ratio - 0.618).abs() < 0.001);
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1175, 1068), (1187, 6, 727, 531)],
+    );
+
+    cmd(&mut carousel, "golden-ratio");
+    assert!((carousel.config.main_ratio - 0.382).abs() < 0.001);
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 726, 1068), (738, 6, 1176, 531)],
+    );
+
+    cmd(&mut carousel, "golden-ratio");
+    assert!((carousel.config.main_ratio - 0.618).abs() < 0.001);
+
+    // The secondary area follows the golden split again too.
+    cmd(&mut carousel, "set-main-ratio-only 0.5");
+    cmd(&mut carousel, "golden-ratio");
+    cmd(&mut carousel, "golden-ratio");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 726, 1068), (738, 6, 1176, 531)],
+    );
+}
+
+#[test]
+fn cached_layouts_match_fresh_ones_until_a_command() {
+    let mut carousel = Carousel::new(Config::default());
+    let fresh = rects(&layout(&mut carousel, 3, 1920, 1080).views);
+    assert_eq!(fresh[0], (6, 6, 1141, 1068));
+
+    let cached = carousel.cached(3, 1920, 1080, 1, "test").unwrap();
+    assert_eq!(rects(&cached.views), fresh);
+    assert_eq!(rects(&layout(&mut carousel, 3, 1920, 1080).views), fresh);
+    assert!(carousel.cached(3, 1080, 1920, 1, "test").is_none());
+    assert!(carousel.cached(3, 1920, 1080, 2, "test").is_none());
+
+    cmd(&mut carousel, "set-main-ratio 0.4");
+    assert!(carousel.cached(3, 1920, 1080, 1, "test").is_none());
+    let mut changed = Carousel::new(Config {
+        main_ratio: 0.4,
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        rects(&layout(&mut changed, 3, 1920, 1080).views),
+    );
+}
+
+#[test]
+fn bare_scroll_moves_by_the_scroll_step() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-scroll-step 2");
+    cmd(&mut carousel, "scroll");
+    assert_eq!(carousel.config.scroll_offset, 2.0);
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, -1068, 761, 531),
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+
+    // An amount overrides the step.
+    cmd(&mut carousel, "scroll -1");
+    assert_eq!(carousel.config.scroll_offset, 1.0);
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views)[2],
+        (1153, 6, 761, 531),
+    );
+}
+
+#[test]
+fn scroll_sensitivity_is_set_per_output() {
+    let mut carousel = Carousel::new(Config::default());
+    carousel
+        .user_cmd("set-scroll-sensitivity 2".into(), None, "wide")
+        .unwrap();
+
+    carousel.user_cmd("scroll 1".into(), None, "wide").unwrap();
+    assert_eq!(carousel.config.scroll_offset, 2.0);
+    let wide = carousel.generate_layout(4, 1920, 1080, 1, "wide").unwrap();
+    assert_eq!(
+        rects(&wide.views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, -1068, 761, 531),
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+        ],
+    );
+
+    // Other outputs keep the configured sensitivity.
+    carousel
+        .user_cmd("scroll -1".into(), None, "small")
+        .unwrap();
+    assert_eq!(carousel.config.scroll_offset, 1.0);
+    let small = carousel.generate_layout(4, 1920, 1080, 1, "small").unwrap();
+    assert_eq!(
+        rects(&small.views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+
+    assert!(matches!(
+        carousel.user_cmd("set-scroll-sensitivity NaN".into(), None, "small"),
+        Err(Error::InvalidArgument("sensitivity")),
+    ));
+}
+
+#[test]
+fn reset_view_clears_transient_state_only() {
+    let mut carousel = Carousel::new(Config::default());
+    let configured = [
+        (6, 6, 760, 1068),
+        (772, 6, 1142, 531),
+        (772, 543, 1142, 531),
+    ];
+    cmd(&mut carousel, "set-main-ratio 0.4");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        configured
+    );
+
+    for command in [
+        "scroll 2",
+        "minimize-main",
+        "rotate-strip",
+        "promote 2",
+        "freeze",
+    ] {
+        cmd(&mut carousel, command);
+        assert_ne!(
+            rects(&layout(&mut carousel, 3, 1920, 1080).views),
+            configured,
+            "{command}",
+        );
+    }
+
+    cmd(&mut carousel, "reset-view");
+    assert_eq!(carousel.config.main_ratio, 0.4);
+    assert_eq!(carousel.config.scroll_offset, 0.0);
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        configured
+    );
+}
+
+#[test]
+fn reset_returns_the_configuration_to_its_defaults() {
+    let mut carousel = Carousel::new(Config {
+        main_location: Edge::Right,
+        scroll_offset: 1.5,
+        ..Config::default()
+    });
+    for command in [
+        "set-main-location top",
+        "mod-main-ratio-only 0.2",
+        "corner-scroll 2",
+        "follow-new",
+        "pin-main",
+    ] {
+        cmd(&mut carousel, command);
+    }
+
+    cmd(&mut carousel, "reset-scroll");
+    assert_eq!(carousel.config.scroll_offset, 0.0);
+    assert_eq!(carousel.config.corner_scroll_offset, 2.0);
+
+    cmd(&mut carousel, "reset keep-location");
+    assert_eq!(carousel.config.main_location, Edge::Top);
+    cmd(&mut carousel, "reset");
+    let default = Config::default();
+    assert_eq!(carousel.config.main_location, default.main_location);
+    assert_eq!(carousel.config.main_ratio, default.main_ratio);
+    assert_eq!(
+        carousel.config.corner_scroll_offset,
+        default.corner_scroll_offset
+    );
+    assert_eq!(carousel.config.follow_new, default.follow_new);
+    assert_eq!(carousel.config.pin_main, default.pin_main);
+    assert_eq!(carousel.secondary_ratio, None);
+}
+
+#[test]
+fn fixed_size_main_areas_leave_the_rest_to_the_strip() {
+    let mut carousel = Carousel::new(Config {
+        main_fixed_size: Some(IVec2::new(640, 360)),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (6, 360, 640, 360),
+            (652, 6, 1262, 531),
+            (652, 543, 1262, 531),
+        ],
+    );
+    cmd(&mut carousel, "set-main-location top");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [(640, 6, 640, 360), (6, 372, 951, 702), (963, 372, 951, 702),],
+    );
+
+    // Sizes past the layout area are clamped, leaving the strip empty.
+    let mut carousel = Carousel::new(Config {
+        main_fixed_size: Some(IVec2::new(4000, 4000)),
+        ..Config::default()
+    });
+    let views = layout(&mut carousel, 2, 1920, 1080).views;
+    assert_eq!(rects(&views)[0], (6, 6, 1902, 1068));
+    assert_within(&views, 1920, 1080);
+}
+
+#[test]
+fn huge_outputs_are_laid_out_at_the_largest_size() {
+    let configs = [
+        Config::default(),
+        Config::builder()
+            .main_location(Edge::Top)
+            .outer_padding(Padding {
+                top: 1,
+                right: 2,
+                bottom: 3,
+                left: 4,
+            })
+            .view_padding(-8)
+            .build()
+            .unwrap(),
+        Config {
+            main_corner: Some(Corner::BottomRight),
+            ..Config::default()
+        },
+        Config {
+            gaps_scale_with_output: true,
+            ..Config::default()
+        },
+    ];
+    for config in configs {
+        let mut carousel = Carousel::new(config);
+        let largest = layout(&mut carousel, 5, MAX_USABLE_SIZE, MAX_USABLE_SIZE);
+        for size in [i32::MAX as u32 + 1, u32::MAX] {
+            let huge = layout(&mut carousel, 5, size, size);
+            assert_eq!(rects(&huge.views), rects(&largest.views));
+        }
+    }
+}
+
+#[test]
+fn far_scrolled_views_saturate() {
+    let mut carousel = Carousel::new(Config {
+        respect_client_aspect: true,
+        ..Config::default()
+    });
+    cmd(&mut carousel, "set-view-aspect 1 1");
+    cmd(&mut carousel, "scroll 1e30");
+    let far = layout(&mut carousel, 3, u32::MAX, u32::MAX);
+    assert_eq!(far.views.len(), 3);
+}
+
+#[test]
+fn view_counts_are_bounded() {
+    let wrapped = || Config {
+        wrap: true,
+        ..Config::default()
+    };
+    let setups: [(Config, &[&str]); 4] = [
+        (Config::default(), &[]),
+        (wrapped(), &[]),
+        (wrapped(), &["overview"]),
+        (Config::default(), &["thumbnail-rail"]),
+    ];
+    for (config, setup) in setups {
+        let mut carousel = Carousel::new(config);
+        for command in setup {
+            cmd(&mut carousel, command);
+        }
+        let most = layout(&mut carousel, MAX_VIEW_COUNT, u32::MAX, u32::MAX);
+        assert_eq!(most.views.len(), MAX_VIEW_COUNT as usize, "{setup:?}");
+        assert!(matches!(
+            carousel.generate_layout(u32::MAX, 1920, 1080, 1, "test"),
+            Err(Error::TooManyViews(u32::MAX)),
+        ));
+    }
+}
+
+#[test]
+fn secondary_windows_from_main_start_next_to_the_main_area() {
+    let cases = [
+        (
+            Edge::Left,
+            Axis::Horizontal,
+            [
+                (6, 6, 1141, 1068),
+                (1153, 6, 377, 1068),
+                (1536, 6, 377, 1068),
+            ],
+        ),
+        (
+            Edge::Right,
+            Axis::Horizontal,
+            [(773, 6, 1141, 1068), (390, 6, 377, 1068), (7, 6, 377, 1068)],
+        ),
+        (
+            Edge::Top,
+            Axis::Vertical,
+            [(6, 6, 1908, 637), (6, 649, 1908, 209), (6, 864, 1908, 209)],
+        ),
+        (
+            Edge::Bottom,
+            Axis::Vertical,
+            [(6, 437, 1908, 637), (6, 222, 1908, 209), (6, 7, 1908, 209)],
+        ),
+    ];
+    for (edge, axis, expected) in cases {
+        let name = format!("{edge:?}");
+        let mut carousel = Carousel::new(Config {
+            main_location: edge,
+            scroll_axis: Some(axis),
+            secondary_from_main: true,
+            ..Config::default()
+        });
+        assert_eq!(
+            rects(&layout(&mut carousel, 3, 1920, 1080).views),
+            expected,
+            "{name}",
+        );
+    }
+}
+
+#[test]
+fn last_layout_is_the_one_last_generated() {
+    let mut carousel = Carousel::new(Config::default());
+    assert!(carousel.last_layout().is_none());
+
+    layout(&mut carousel, 3, 1920, 1080);
+    let generated = layout(&mut carousel, 2, 1080, 1920);
+    let last = carousel.last_layout().unwrap();
+    assert_eq!(last.layout_name, generated.layout_name);
+    assert_eq!(rects(&last.views), [(6, 6, 637, 1908), (649, 6, 425, 951)],);
+    assert_eq!(rects(&last.views), rects(&generated.views));
+
+    // Failed commands leave it alone.
+    assert!(carousel.user_cmd("bogus".into(), None, "test").is_err());
+    assert_eq!(
+        rects(&carousel.last_layout().unwrap().views),
+        rects(&generated.views),
+    );
+}
+
+#[test]
+fn follow_new_scrolls_to_the_newest_window() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "follow-new");
+    layout(&mut carousel, 3, 1920, 1080);
+
+    // The fifth window is last, at the bottom of the strip.
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, -1068, 761, 531),
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+    assert_eq!(carousel.config.scroll_offset, 2.0);
+
+    // Closing a window doesn't scroll back.
+    layout(&mut carousel, 4, 1920, 1080);
+    assert_eq!(carousel.config.scroll_offset, 2.0);
+
+    cmd(&mut carousel, "follow-new");
+    cmd(&mut carousel, "reset-scroll");
+    layout(&mut carousel, 6, 1920, 1080);
+    assert_eq!(carousel.config.scroll_offset, 0.0);
+}
+
+#[test]
+fn aliases_expand_to_their_commands() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "alias + main-ratio 0.1");
+    cmd(&mut carousel, "alias ratio set-main-ratio");
+
+    cmd(&mut carousel, "+");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1331, 1068), (1343, 6, 571, 531)],
+    );
+
+    // Arguments are passed on after the expansion.
+    cmd(&mut carousel, "ratio 0.4");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 760, 1068), (772, 6, 1142, 531)],
+    );
+    assert!(matches!(
+        carousel.user_cmd("ratio".into(), None, "test"),
+        Err(Error::MissingArgument("ratio")),
+    ));
+    assert!(matches!(
+        carousel.user_cmd("alias -".into(), None, "test"),
+        Err(Error::MissingArgument("command")),
+    ));
+}
+
+#[test]
+fn pinned_main_view_stays_while_scrolling() {
+    let mut carousel = Carousel::new(Config {
+        pin_main: true,
+        ..Config::default()
+    });
+    let main_area = rects(&layout(&mut carousel, 5, 1920, 1080).views)[0];
+    cmd(&mut carousel, "promote 3");
+    let promoted = rects(&layout(&mut carousel, 5, 1920, 1080).views);
+    assert_eq!(promoted[3], main_area);
+
+    cmd(&mut carousel, "scroll 1");
+    let scrolled = rects(&layout(&mut carousel, 5, 1920, 1080).views);
+    assert_eq!(scrolled[3], main_area);
+    assert_ne!(scrolled[0], promoted[0]);
+
+    // Unpinned, scrolling returns the main area to the first view.
+    cmd(&mut carousel, "pin-main");
+    cmd(&mut carousel, "scroll 1");
+    let unpinned = rects(&layout(&mut carousel, 5, 1920, 1080).views);
+    assert_eq!(unpinned[0], main_area);
+}
+
+#[test]
+fn promoting_moves_every_main_area() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-main-count 2");
+    let main_areas = rects(&layout(&mut carousel, 6, 1920, 1080).views)[..2].to_vec();
+    cmd(&mut carousel, "promote 3");
+    let promoted = rects(&layout(&mut carousel, 6, 1920, 1080).views);
+    assert_eq!(promoted[3..5], main_areas);
+    cmd(&mut carousel, "pin-main");
+    cmd(&mut carousel, "scroll 1");
+    let pinned = rects(&layout(&mut carousel, 6, 1920, 1080).views);
+    assert_eq!(pinned[3..5], main_areas);
+
+    // The main areas stay whole at the end of the stack.
+    cmd(&mut carousel, "promote 5");
+    let promoted = rects(&layout(&mut carousel, 6, 1920, 1080).views);
+    assert_eq!(promoted[4..], main_areas);
+
+    let mut carousel = Carousel::new(Config {
+        main_tag: Some(4),
+        ..Config::default()
+    });
+    cmd(&mut carousel, "set-main-count 2");
+    cmd(&mut carousel, "set-view-tags 2 4");
+    let tagged = rects(&layout(&mut carousel, 6, 1920, 1080).views);
+    assert_eq!(tagged[2..4], main_areas);
+}
+
+#[test]
+fn promote_defaults_to_the_window_scrolled_to() {
+    let mut carousel = Carousel::new(Config::default());
+    let main_area = rects(&layout(&mut carousel, 5, 1920, 1080).views)[0];
+    cmd(&mut carousel, "scroll 2");
+    layout(&mut carousel, 5, 1920, 1080);
+    cmd(&mut carousel, "promote");
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views)[3],
+        main_area
+    );
+}
+
+#[test]
+fn main_ratio_commands_keep_within_bounds() {
+    let mut carousel = Carousel::new(Config::default());
+    for ratio in ["0", "1", "-0.1", "1.5", "NaN", "inf", "half"] {
+        assert!(
+            matches!(
+                carousel.user_cmd(format!("set-main-ratio {ratio}"), None, "test"),
+                Err(Error::InvalidArgument("ratio")),
+            ),
+            "{ratio}",
+        );
+    }
+
+    // Ratios just inside the bounds still leave valid views.
+    for ratio in ["0.001", "0.999"] {
+        cmd(&mut carousel, &format!("set-main-ratio {ratio}"));
+        for (width, height) in OUTPUT_SIZES {
+            assert_within(
+                &layout(&mut carousel, 2, width, height).views,
+                width,
+                height,
+            );
+        }
+    }
+
+    cmd(&mut carousel, "set-main-ratio 0.6");
+    cmd(&mut carousel, "main-ratio +0.05");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1236, 1068), (1248, 6, 666, 531)],
+    );
+
+    // Steps past the ends are clamped.
+    cmd(&mut carousel, "main-ratio 1");
+    assert_eq!(carousel.config.main_ratio, 0.95);
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1806, 1068), (1818, 6, 96, 531)],
+    );
+    cmd(&mut carousel, "main-ratio -5");
+    assert_eq!(carousel.config.main_ratio, 0.05);
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 95, 1068), (107, 6, 1807, 531)],
+    );
+}
+
+#[test]
+fn logical_padding_scales_with_the_output() {
+    let mut carousel = Carousel::new(Config {
+        logical_padding: true,
+        ..Config::default()
+    });
+    cmd(&mut carousel, "set-output-scale 2");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (12, 12, 1130, 1056),
+            (1154, 12, 754, 522),
+            (1154, 546, 754, 522),
+        ],
+    );
+
+    // Other outputs, and physical padding, are unscaled.
+    let other = carousel.generate_layout(3, 1920, 1080, 1, "other").unwrap();
+    assert_eq!(rects(&other.views)[0], (6, 6, 1141, 1068));
+    let mut physical = Carousel::new(Config::default());
+    cmd(&mut physical, "set-output-scale 2");
+    assert_eq!(
+        rects(&layout(&mut physical, 3, 1920, 1080).views)[0],
+        (6, 6, 1141, 1068),
+    );
+}
+
+#[test]
+fn secondary_size_commands_change_how_many_windows_are_shown() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-secondary-size 0.25");
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 262),
+            (1153, 274, 761, 262),
+            (1153, 542, 761, 262),
+            (1153, 810, 761, 262),
+        ],
+    );
+
+    // Two windows at a time again.
+    cmd(&mut carousel, "secondary-size +0.25");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+    cmd(&mut carousel, "secondary-size -1");
+    assert_eq!(carousel.config.secondary_window_size, 0.05);
+
+    for size in ["0", "1", "-0.5", "NaN"] {
+        assert!(
+            matches!(
+                carousel.user_cmd(format!("set-secondary-size {size}"), None, "test"),
+                Err(Error::InvalidArgument("size")),
+            ),
+            "{size}",
+        );
+    }
+}
+
+#[test]
+fn set_main_location_moves_the_main_area_and_keeps_the_scroll() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "scroll 1");
+    let edges = [
+        ("Top", (6, 6, 1908, 637)),
+        ("RIGHT", (773, 6, 1141, 1068)),
+        ("bottom", (6, 437, 1908, 637)),
+        ("left", (6, 6, 1141, 1068)),
+    ];
+    for (edge, main) in edges {
+        cmd(&mut carousel, &format!("set-main-location {edge}"));
+        assert_eq!(
+            rects(&layout(&mut carousel, 3, 1920, 1080).views)[0],
+            main,
+            "{edge}",
+        );
+        assert_eq!(carousel.config.scroll_offset, 1.0);
+    }
+
+    assert!(matches!(
+        carousel.user_cmd("set-main-location middle".into(), None, "test"),
+        Err(Error::InvalidArgument("location")),
+    ));
+}
+
+#[test]
+fn main_aspect_shrinks_the_main_area_within_its_ratio() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-main-aspect 1");
+    let views = rects(&layout(&mut carousel, 2, 1920, 1080).views);
+    assert_eq!(views[0], (42, 6, 1068, 1068));
+    // The secondary area is where it would be without the aspect.
+    assert_eq!(views[1], (1153, 6, 761, 531));
+
+    cmd(&mut carousel, "set-main-aspect 4");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views)[0],
+        (6, 397, 1141, 285),
+    );
+
+    for (width, height) in OUTPUT_SIZES {
+        for aspect in ["0.25", "1", "16:9"] {
+            cmd(&mut carousel, &format!("set-main-aspect {aspect}"));
+            let main = layout(&mut carousel, 2, width, height).views[0];
+            let unconstrained =
+                layout(&mut Carousel::new(Config::default()), 2, width, height).views[0];
+            assert!(main.width <= unconstrained.width && main.height <= unconstrained.height);
+        }
+    }
+}
+
+#[test]
+fn describe_summarizes_the_last_layout() {
+    let mut carousel = Carousel::new(Config::default());
+    assert_eq!(
+        carousel.describe(),
+        "carousel: main left at 60%, 0 of 0 visible, scroll 0.0",
+    );
+
+    cmd(&mut carousel, "set-main-location top");
+    cmd(&mut carousel, "scroll 2");
+    let views = layout(&mut carousel, 8, 1920, 1080).views;
+    assert_eq!(rects(&views)[0], (6, 6, 1908, 637));
+    assert_eq!(
+        carousel.describe(),
+        "carousel: main top at 60%, 2 of 7 visible, scroll 2.0",
+    );
+
+    cmd(&mut carousel, "set-secondary-size 0.25");
+    layout(&mut carousel, 3, 1920, 1080);
+    assert_eq!(
+        carousel.describe(),
+        "carousel: main top at 60%, 2 of 2 visible, scroll 2.0",
+    );
+
+    let cornered = Carousel::new(Config {
+        main_corner: Some(Corner::TopLeft),
+        ..Config::default()
+    });
+    assert_eq!(
+        cornered.describe(),
+        "carousel: main top-left at 60%, 0 of 0 visible, scroll 0.0",
+    );
+}
+
+#[test]
+fn clamped_scrolling_stops_at_the_last_window() {
+    let clamped = || {
+        Carousel::new(Config {
+            clamp_scroll: true,
+            ..Config::default()
+        })
+    };
+
+    // With no secondary windows, or too few to fill the strip, there is
+    // nothing to scroll.
+    for view_count in [1, 2] {
+        let mut carousel = clamped();
+        cmd(&mut carousel, "scroll 5");
+        layout(&mut carousel, view_count, 1920, 1080);
+        assert_eq!(carousel.config.scroll_offset, 0.0, "{view_count} views");
+    }
+    let mut carousel = clamped();
+    cmd(&mut carousel, "scroll 5");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1141, 1068), (1153, 6, 761, 531)],
+    );
+
+    // Nine secondary windows, two at a time, scroll at most seven windows.
+    let mut carousel = clamped();
+    cmd(&mut carousel, "scroll 100");
+    let views = rects(&layout(&mut carousel, 10, 1920, 1080).views);
+    assert_eq!(carousel.config.scroll_offset, 7.0);
+    assert_eq!(views[8..], [(1153, 6, 761, 531), (1153, 543, 761, 531)]);
+    cmd(&mut carousel, "scroll -100");
+    let views = rects(&layout(&mut carousel, 10, 1920, 1080).views);
+    assert_eq!(carousel.config.scroll_offset, 0.0);
+    assert_eq!(views[1..3], [(1153, 6, 761, 531), (1153, 543, 761, 531)]);
+
+    // Free scrolling is the default.
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "scroll 100");
+    layout(&mut carousel, 10, 1920, 1080);
+    assert_eq!(carousel.config.scroll_offset, 100.0);
+}
+
+#[test]
+fn wrapped_scrolling_loops_around_the_secondary_windows() {
+    let wrapped = |scroll_offset| {
+        let mut carousel = Carousel::new(Config {
+            wrap: true,
+            scroll_offset,
+            ..Config::default()
+        });
+        rects(&layout(&mut carousel, 5, 1920, 1080).views)
+    };
+
+    // Scrolling through all four secondary windows comes back to the start.
+    let start = wrapped(0.0);
+    assert_eq!(
+        start,
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+            (1153, 1080, 761, 531),
+            (1153, 1617, 761, 531),
+        ],
+    );
+    assert_eq!(wrapped(4.0), start);
+    assert_eq!(wrapped(-8.0), start);
+
+    // Half a window back, the last window peeks in before the first.
+    let before = wrapped(-0.5);
+    assert_eq!(
+        before,
+        [
+            (6, 6, 1141, 1068),
+            (1153, 274, 761, 531),
+            (1153, 811, 761, 531),
+            (1153, 1348, 761, 531),
+            (1153, -263, 761, 531),
+        ],
+    );
+    assert_eq!(wrapped(3.5), before);
+}
+
+#[test]
+fn wrapped_scrolling_never_leaves_a_gap() {
+    // Four secondary windows for two slots, and two for four slots.
+    for (view_count, secondary_window_size) in [(5, 0.5), (3, 0.25)] {
+        let mut carousel = Carousel::new(Config {
+            wrap: true,
+            scroll_offset: 2.5,
+            secondary_window_size,
+            ..Config::default()
+        });
+        let mut views = layout(&mut carousel, view_count, 1920, 1080).views;
+        let secondary = &mut views[1..];
+        secondary.sort_by_key(|view| view.y);
+
+        // The windows run from before the top of the secondary area to past
+        // its bottom, each 6 pixels after the one before.
+        assert!(secondary[0].y < 6, "{secondary:?}");
+        for pair in secondary.windows(2) {
+            assert_eq!(pair[1].y, pair[0].y + pair[0].height as i32 + 6);
+        }
+        let last = secondary.last().unwrap();
+        assert!(last.y + last.height as i32 > 1074, "{secondary:?}");
+    }
+
+    // A single secondary window stays put, filling the secondary area.
+    let mut carousel = Carousel::new(Config {
+        wrap: true,
+        scroll_offset: 2.5,
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1141, 1068), (1153, 6, 761, 1068)],
+    );
+}
+
+#[test]
+fn snapping_rounds_the_scroll_to_whole_windows() {
+    let snapped = |scroll_offset, command| {
+        let mut carousel = Carousel::new(Config {
+            scroll_offset,
+            ..Config::default()
+        });
+        cmd(&mut carousel, command);
+        carousel.config.scroll_offset
+    };
+    assert_eq!(snapped(2.4, "snap"), 2.0);
+    assert_eq!(snapped(2.6, "snap"), 3.0);
+    assert_eq!(snapped(2.4, "snap-next"), 3.0);
+    assert_eq!(snapped(2.0, "snap-next"), 3.0);
+    assert_eq!(snapped(2.6, "snap-prev"), 2.0);
+    assert_eq!(snapped(2.0, "snap-prev"), 1.0);
+
+    // Snapped windows line up with the edge of the secondary area again.
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "scroll 0.6");
+    cmd(&mut carousel, "scroll 0.6");
+    cmd(&mut carousel, "snap");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views)[1..],
+        [
+            (1153, -531, 761, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+}
+
+#[test]
+fn restore_leaves_the_overview() {
+    let mut carousel = Carousel::new(Config::default());
+    let carousel_views = rects(&layout(&mut carousel, 5, 1920, 1080).views);
+
+    cmd(&mut carousel, "overview");
+    let overview = layout(&mut carousel, 5, 1920, 1080);
+    assert_eq!(overview.layout_name, "carousel: overview");
+
+    cmd(&mut carousel, "restore");
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        carousel_views
+    );
+    assert!(matches!(
+        carousel.user_cmd("restore".into(), None, "test"),
+        Err(Error::State {
+            operation: "restore",
+            ..
+        }),
+    ));
+}
+
+#[test]
+fn overview_fits_every_window_without_overlap() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "scroll 3");
+    cmd(&mut carousel, "overview");
+    assert_eq!(
+        rects(&layout(&mut carousel, 5, 1920, 1080).views),
+        [
+            (6, 6, 632, 531),
+            (644, 6, 632, 531),
+            (1282, 6, 632, 531),
+            (6, 543, 632, 531),
+            (644, 543, 632, 531),
+        ],
+    );
+
+    let overlap = |a: &Rectangle, b: &Rectangle| {
+        a.x < b.x + b.width as i32
+            && b.x < a.x + a.width as i32
+            && a.y < b.y + b.height as i32
+            && b.y < a.y + a.height as i32
+    };
+    for (width, height) in OUTPUT_SIZES {
+        for view_count in 1..13 {
+            let views = layout(&mut carousel, view_count, width, height).views;
+            assert_within(&views, width, height);
+            for (index, view) in views.iter().enumerate() {
+                assert!(view.width > 0 && view.height > 0, "{view:?}");
+                for other in &views[index + 1..] {
+                    assert!(!overlap(view, other), "{view:?} overlaps {other:?}");
+                }
+            }
+        }
+    }
+}
+
+/// A fresh carousel with the default configuration, brought to the state of
+/// `carousel` by replaying its `dump-cmd` commands.
+fn replayed(carousel: &Carousel) -> Carousel {
+    let mut replayed = Carousel::new(Config::default());
+    for command in carousel.dump_commands() {
+        replayed
+            .user_cmd(command.clone(), None, "test")
+            .unwrap_or_else(|error| panic!("{command}: {error:?}"));
+    }
+    replayed
+}
+
+#[test]
+fn dumped_commands_replay_to_the_same_configuration() {
+    let setups: [&[&str]; 4] = [
+        &[],
+        &[
+            "set-main-location top",
+            "set-main-ratio 0.3",
+            "mod-main-ratio-only 0.7",
+            "secondary-size -0.1",
+            "set-view-padding -3",
+            "set-scroll-axis vertical",
+            "set-main-aspect 16:9",
+            "scroll 2.5",
+            "corner-scroll -1",
+            "thumbnail-rail",
+            "set-rail-height 40",
+            "pin-main",
+            "alias next scroll 1",
+        ],
+        &[
+            "main-ratio 0.1",
+            "mod-main-ratio-only -0.05",
+            "set-main-ratio-only 0.9",
+        ],
+        &[
+            "golden-ratio",
+            "set-main-count 2",
+            "follow-new",
+            "set-min-view-size 7",
+        ],
+    ];
+    for setup in setups {
+        let mut carousel = Carousel::new(Config::default());
+        for command in setup {
+            cmd(&mut carousel, command);
+        }
+        let replayed = replayed(&carousel);
+        assert!(replayed.config == carousel.config, "{setup:?}");
+        assert_eq!(
+            replayed.secondary_ratio, carousel.secondary_ratio,
+            "{setup:?}"
+        );
+        assert_eq!(replayed.state_hash(), carousel.state_hash(), "{setup:?}");
+    }
+
+    // Values from the configuration that no command accepts are left out,
+    // rather than dumped as commands that fail.
+    let carousel = Carousel::new(Config {
+        main_ratio: 1.0,
+        view_padding: i32::MAX,
+        scroll_offset: f32::NAN,
+        ..Config::default()
+    });
+    replayed(&carousel);
+}
+
+#[test]
+fn bottom_main_areas_end_at_the_bottom_padding() {
+    let mut carousel = Carousel::new(Config {
+        main_location: Edge::Bottom,
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 2560, 1440).views),
+        [(6, 581, 2548, 853), (6, 6, 1271, 569), (1283, 6, 1271, 569),],
+    );
+    for (width, height) in OUTPUT_SIZES {
+        let main = layout(&mut carousel, 3, width, height).views[0];
+        assert_eq!(
+            main.y + main.height as i32,
+            height as i32 - 6,
+            "{width}x{height}"
+        );
+    }
+}
+
+#[test]
+fn degenerate_usable_areas_give_empty_views() {
+    let cases = [(0, 1080, 6), (1920, 0, 6), (1, 1, 6), (1920, 1080, 2000)];
+    for (width, height, padding) in cases {
+        let mut carousel = Carousel::new(Config {
+            outer_padding: Padding::uniform(padding),
+            ..Config::default()
+        });
+        let views = layout(&mut carousel, 3, width, height).views;
+        assert_eq!(views.len(), 3);
+        for view in &views {
+            assert!(
+                view.width == 0 || view.height == 0,
+                "{width}x{height}: {view:?}"
+            );
+        }
+    }
+
+    let mut carousel = Carousel::new(Config::default());
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 0).views),
+        [(6, 6, 1141, 0), (1153, 6, 761, 0), (1153, 12, 761, 0)],
+    );
+}
+
+#[test]
+fn rotating_the_strip_keeps_the_main_area_in_place() {
+    let mut carousel = Carousel::new(Config::default());
+    let vertical = rects(&layout(&mut carousel, 4, 1920, 1080).views);
+    assert_eq!(
+        vertical,
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+            (1153, 1080, 761, 531),
+        ],
+    );
+
+    cmd(&mut carousel, "rotate-strip");
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 377, 1068),
+            (1536, 6, 377, 1068),
+            (1919, 6, 377, 1068),
+        ],
+    );
+
+    cmd(&mut carousel, "rotate-strip");
+    assert_eq!(rects(&layout(&mut carousel, 4, 1920, 1080).views), vertical);
+}
+
+#[test]
+fn hashed_bytes_finish_with_their_fnv_hash() {
+    let mut bytes = HashedBytes::default();
+    bytes.write(b"a");
+    assert_eq!(bytes.finish(), 0xaf63dc4c8601ec8c);
+    assert_eq!(HashedBytes::default().finish(), 0xcbf29ce484222325);
+}
+
+#[test]
+fn equal_configs_hash_the_same() {
+    let default = HashedBytes::of(&Config::default());
+    assert_eq!(
+        default.finish(),
+        HashedBytes::of(&Config::default()).finish()
+    );
+    assert_ne!(
+        default.finish(),
+        HashedBytes::of(&Config {
+            main_ratio: 0.5,
+            ..Config::default()
+        })
+        .finish()
+    );
+    assert!(Config::default() == Config::default());
+    assert!(
+        Config::default()
+            != Config {
+                main_ratio: 0.5,
+                ..Config::default()
+            }
+    );
+}
+
+#[test]
+fn offscreen_windows_are_collapsed_at_the_nearest_edge() {
+    let mut carousel = Carousel::new(Config {
+        hide_offscreen: true,
+        scroll_offset: 10.5,
+        ..Config::default()
+    });
+    let views = rects(&layout(&mut carousel, 20, 1920, 1080).views);
+
+    // Only the main area and the secondary windows 10 to 12 are on screen.
+    let shown: Vec<_> = (0..20)
+        .filter(|&index| views[index].2 > 0 && views[index].3 > 0)
+        .collect();
+    assert_eq!(shown, [0, 11, 12, 13]);
+    assert_eq!(
+        views[11..14],
+        [
+            (1153, -262, 761, 531),
+            (1153, 275, 761, 531),
+            (1153, 812, 761, 531),
+        ],
+    );
+    assert_eq!(views[10], (1153, 0, 0, 0));
+    assert_eq!(views[14], (1153, 1080, 0, 0));
+
+    // Without the option, every window keeps its size.
+    let mut carousel = Carousel::new(Config {
+        scroll_offset: 10.5,
+        ..Config::default()
+    });
+    let views = layout(&mut carousel, 20, 1920, 1080).views;
+    assert!(views.iter().all(|view| view.width > 0 && view.height > 0));
+}
+
+#[test]
+fn focus_scale_shrinks_windows_away_from_the_center() {
+    let scaled = |focus_scale| {
+        let mut carousel = Carousel::new(Config {
+            focus_scale,
+            secondary_window_size: 1.0 / 3.0,
+            ..Config::default()
+        });
+        rects(&layout(&mut carousel, 4, 1920, 1080).views)
+    };
+
+    assert_eq!(
+        scaled(1.0),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 352),
+            (1153, 364, 761, 352),
+            (1153, 722, 761, 352),
+        ],
+    );
+    // The main area and the centered window are full size, and the windows
+    // at the ends shrink around their own centers.
+    assert_eq!(
+        scaled(0.5),
+        [
+            (6, 6, 1141, 1068),
+            (1281, 65, 505, 234),
+            (1153, 364, 761, 352),
+            (1281, 781, 505, 234),
+        ],
+    );
+}
+
+#[test]
+fn peeking_main_areas_are_revealed_on_command() {
+    let mut carousel = Carousel::new(Config {
+        peek_main: true,
+        ..Config::default()
+    });
+    let peeking = [(6, 6, 24, 1068), (36, 6, 1878, 531)];
+    assert_eq!(rects(&layout(&mut carousel, 2, 1920, 1080).views), peeking);
+
+    cmd(&mut carousel, "reveal-main");
+    assert_eq!(
+        rects(&layout(&mut carousel, 2, 1920, 1080).views),
+        [(6, 6, 1141, 1068), (1153, 6, 761, 531)],
+    );
+
+    cmd(&mut carousel, "reveal-main");
+    assert_eq!(rects(&layout(&mut carousel, 2, 1920, 1080).views), peeking);
+}
+
+#[test]
+fn main_count_splits_the_main_area() {
+    let split = |main_count: u32, view_count| {
+        let mut carousel = Carousel::new(Config::default());
+        cmd(&mut carousel, &format!("set-main-count {main_count}"));
+        rects(&layout(&mut carousel, view_count, 1920, 1080).views)
+    };
+
+    assert_eq!(
+        split(1, 3),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+    assert_eq!(
+        split(2, 4),
+        [
+            (6, 6, 1141, 531),
+            (6, 543, 1141, 531),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+    assert_eq!(
+        split(3, 5),
+        [
+            (6, 6, 1141, 352),
+            (6, 364, 1141, 352),
+            (6, 722, 1141, 352),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+    // With fewer views than that, they all share the main area.
+    assert_eq!(split(3, 2), [(6, 6, 1141, 531), (6, 543, 1141, 531)]);
+}
+
+#[test]
+fn scroll_to_centers_the_given_window() {
+    let mut carousel = Carousel::new(Config::default());
+    assert!(matches!(
+        carousel.user_cmd("scroll-to 0".into(), None, "test"),
+        Err(Error::InvalidArgument("index")),
+    ));
+
+    layout(&mut carousel, 8, 1920, 1080);
+    cmd(&mut carousel, "scroll-to 5");
+    assert_eq!(carousel.config.scroll_offset, 4.5);
+    let views = rects(&layout(&mut carousel, 8, 1920, 1080).views);
+    assert_eq!(views[6], (1153, 275, 761, 531));
+
+    // There are only seven secondary windows.
+    assert!(matches!(
+        carousel.user_cmd("scroll-to 7".into(), None, "test"),
+        Err(Error::InvalidArgument("index")),
+    ));
+    assert_eq!(carousel.config.scroll_offset, 4.5);
+}
+
+#[test]
+fn each_set_of_tags_keeps_its_own_scroll_offset() {
+    let mut carousel = Carousel::new(Config::default());
+    let secondary = |carousel: &mut Carousel, tags| {
+        let layout = carousel
+            .generate_layout(4, 1920, 1080, tags, "test")
+            .unwrap();
+        rects(&layout.views)[1]
+    };
+
+    assert_eq!(secondary(&mut carousel, 1), (1153, 6, 761, 531));
+    cmd(&mut carousel, "scroll 2");
+    assert_eq!(secondary(&mut carousel, 1), (1153, -1068, 761, 531));
+
+    // Tags seen for the first time start at the beginning of the strip.
+    assert_eq!(secondary(&mut carousel, 2), (1153, 6, 761, 531));
+    cmd(&mut carousel, "scroll 1");
+    assert_eq!(secondary(&mut carousel, 2), (1153, -531, 761, 531));
+
+    assert_eq!(secondary(&mut carousel, 1), (1153, -1068, 761, 531));
+    assert_eq!(secondary(&mut carousel, 2), (1153, -531, 761, 531));
+    assert_eq!(secondary(&mut carousel, 3), (1153, 6, 761, 531));
+
+    // Even when the first tags were given an offset to start at.
+    let mut carousel = Carousel::new(Config {
+        scroll_offset: 2.0,
+        ..Config::default()
+    });
+    assert_eq!(secondary(&mut carousel, 1), (1153, -1068, 761, 531));
+    assert_eq!(secondary(&mut carousel, 2), (1153, 6, 761, 531));
+}
+
+#[test]
+fn secondary_aspect_letterboxes_each_slot() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "set-secondary-aspect 16:9");
+    let views = rects(&layout(&mut carousel, 3, 1920, 1080).views);
+    assert_eq!(
+        views,
+        [
+            (6, 6, 1141, 1068),
+            (1153, 57, 761, 428),
+            (1153, 594, 761, 428),
+        ],
+    );
+
+    // Narrower than the slot, centered across the strip instead.
+    cmd(&mut carousel, "set-secondary-aspect 1");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views)[1..],
+        [(1268, 6, 531, 531), (1268, 543, 531, 531)],
+    );
+
+    cmd(&mut carousel, "set-secondary-aspect none");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views)[1],
+        (1153, 6, 761, 531),
+    );
+}
+
+#[test]
+fn builder_validates_the_configuration() {
+    let config = Config::builder()
+        .main_location(Edge::Top)
+        .main_ratio(0.5)
+        .secondary_window_size(0.25)
+        .outer_padding(10)
+        .view_padding(4)
+        .scroll_offset(1.0)
+        .build()
+        .unwrap();
+    let mut carousel = Carousel::new(config);
+    assert_eq!(
+        rects(&layout(&mut carousel, 4, 1920, 1080).views),
+        [
+            (10, 10, 1900, 528),
+            (-466, 542, 472, 528),
+            (10, 542, 472, 528),
+            (486, 542, 472, 528),
+        ],
+    );
+
+    let failures = [
+        (Config::builder().main_ratio(0.0), "main_ratio"),
+        (Config::builder().main_ratio(1.0), "main_ratio"),
+        (Config::builder().main_ratio(f32::NAN), "main_ratio"),
+        (
+            Config::builder().secondary_window_size(0.0),
+            "secondary_window_size",
+        ),
+        (
+            Config::builder().secondary_window_size(1.5),
+            "secondary_window_size",
+        ),
+        (Config::builder().outer_padding(-1), "outer_padding"),
+        (
+            Config::builder().scroll_offset(f32::INFINITY),
+            "scroll_offset",
+        ),
+    ];
+    for (builder, field) in failures {
+        assert!(
+            matches!(builder.build(), Err(Error::InvalidArgument(name)) if name == field),
+            "{field}",
+        );
+    }
+}
+
+#[test]
+fn state_errors_describe_the_state_and_change_nothing() {
+    let mut carousel = Carousel::new(Config::default());
+    cmd(&mut carousel, "scroll 1");
+    let before = rects(&layout(&mut carousel, 4, 1920, 1080).views);
+    assert_eq!(before[1], (1153, -531, 761, 531));
+    let hash = carousel.state_hash();
+
+    let error = carousel
+        .user_cmd("unfreeze".into(), None, "test")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "cannot unfreeze: the layout isn't frozen"
+    );
+    let error = carousel
+        .user_cmd("restore".into(), None, "test")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "cannot restore: the overview isn't shown"
+    );
+    assert_eq!(carousel.state_hash(), hash);
+    assert_eq!(rects(&layout(&mut carousel, 4, 1920, 1080).views), before);
+
+    let error = Carousel::new(Config::default())
+        .user_cmd("promote".into(), None, "test")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "cannot promote: no layout has been generated yet"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn config_files_round_trip() {
+    let path = std::env::temp_dir().join(format!("carousel-{}.toml", std::process::id()));
+    let document = r#"
+        main_location = "top"
+        main_ratio = 0.5
+        outer_padding = { top = 10 }
+
+        [aliases]
+        next = "scroll 1"
+    "#;
+    std::fs::write(&path, document).unwrap();
+    let config = Config::from_file(&path).unwrap();
+
+    // Saving the configuration and loading it again gives the same one.
+    std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert!(Config::from_file(&path).unwrap() == config);
+
+    let mut carousel = Carousel::new(config);
+    cmd(&mut carousel, "next");
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [
+            (0, 10, 1920, 532),
+            (-963, 548, 957, 532),
+            (0, 548, 957, 532),
+        ],
+    );
+
+    std::fs::write(&path, "main_location = \"middle\"").unwrap();
+    assert!(matches!(
+        Config::from_file(&path),
+        Err(Error::ParseConfig { .. }),
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(Config::from_file(&path).unwrap() == Config::default());
+}
+
+#[test]
+fn thumbnail_rail_runs_along_the_bottom_under_the_main_area() {
+    let mut carousel = Carousel::new(Config {
+        main_location: Edge::Right,
+        ..Config::default()
+    });
+    cmd(&mut carousel, "thumbnail-rail");
+    cmd(&mut carousel, "set-rail-height 100");
+    let generated = layout(&mut carousel, 4, 1920, 1080);
+    assert_eq!(generated.layout_name, "carousel: rail");
+    assert_eq!(
+        rects(&generated.views),
+        [
+            (6, 6, 1908, 962),
+            (6, 974, 951, 100),
+            (963, 974, 951, 100),
+            (1920, 974, 951, 100),
+        ],
+    );
+
+    // Scrolling moves the thumbnails along the rail, but not the main area.
+    cmd(&mut carousel, "scroll 1");
+    let scrolled = rects(&layout(&mut carousel, 4, 1920, 1080).views);
+    assert_eq!(scrolled[0], (6, 6, 1908, 962));
+    assert_eq!(
+        scrolled[1..],
+        [
+            (-951, 974, 951, 100),
+            (6, 974, 951, 100),
+            (963, 974, 951, 100),
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn partial_configs_fill_in_defaults() {
+    assert!(toml::from_str::<Config>("").unwrap() == Config::default());
+
+    let config: Config = toml::from_str("main_location = \"left\"\nview_padding = 0").unwrap();
+    assert!(
+        config
+            == Config {
+                main_location: Edge::Left,
+                view_padding: 0,
+                ..Config::default()
+            }
+    );
+    assert!(toml::from_str::<Config>(&toml::to_string(&config).unwrap()).unwrap() == config);
+    assert_eq!(
+        rects(&layout(&mut Carousel::new(config), 3, 1920, 1080).views),
+        [
+            (6, 6, 1144, 1068),
+            (1150, 6, 764, 534),
+            (1150, 540, 764, 534),
+        ],
+    );
+}
+
+#[test]
+fn state_hash_changes_with_the_layout() {
+    let mut carousel = Carousel::new(Config::default());
+    let before = rects(&layout(&mut carousel, 3, 1920, 1080).views);
+    let hash = carousel.state_hash();
+    assert_eq!(format!("{hash:016x}").len(), 16);
+    cmd(&mut carousel, "state-hash");
+    assert_eq!(carousel.state_hash(), hash);
+
+    cmd(&mut carousel, "set-main-location top");
+    assert_ne!(carousel.state_hash(), hash);
+    assert_eq!(
+        rects(&layout(&mut carousel, 3, 1920, 1080).views),
+        [(6, 6, 1908, 637), (6, 649, 951, 425), (963, 649, 951, 425),],
+    );
+
+    // Getting back to the same state gives the same hash and layout.
+    cmd(&mut carousel, "set-main-location left");
+    assert_eq!(carousel.state_hash(), hash);
+    assert_eq!(rects(&layout(&mut carousel, 3, 1920, 1080).views), before);
+
+    // So does the same state reached on a fresh instance.
+    let mut fresh = Carousel::new(Config::default());
+    layout(&mut fresh, 3, 1920, 1080);
+    assert_eq!(fresh.state_hash(), hash);
+}
+
+#[test]
+fn corner_layouts_are_named_by_the_scroll_offset() {
+    let mut carousel = Carousel::new(Config {
+        main_corner: Some(Corner::TopLeft),
+        ..Config::default()
+    });
+    assert_eq!(
+        layout(&mut carousel, 5, 1920, 1080).layout_name,
+        "carousel: top-left @0.0"
+    );
+
+    cmd(&mut carousel, "scroll 1");
+    cmd(&mut carousel, "corner-scroll 2");
+    assert_eq!(
+        layout(&mut carousel, 5, 1920, 1080).layout_name,
+        "carousel: top-left @1.0"
+    );
+}
+
+#[test]
+fn main_window_is_centered_in_its_split() {
+    // Both padded dimensions are odd, leaving an odd number of pixels around
+    // the main window.
+    let (width, height) = (1921, 1081);
+    for fixed in [false, true] {
+        for edge in [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom] {
+            // Either way, the main window is shrunk along its edge.
+            let aspect = match edge {
+                Edge::Left | Edge::Right => 2.0,
+                Edge::Top | Edge::Bottom => 1.0,
+            };
+            let config = Config {
+                main_location: edge,
+                main_aspect: (!fixed).then_some(aspect),
+                main_fixed_size: fixed.then_some(IVec2::new(1000, 500)),
+                ..Config::default()
+            };
+            let padding = config.outer_padding;
+            let mut carousel = Carousel::new(config);
+            let main = layout(&mut carousel, 3, width, height).views[0];
+
+            // Gaps along the edge, to either end of the padded area.
+            let (before, after) = match edge {
+                Edge::Left | Edge::Right => (
+                    main.y - padding.top,
+                    height as i32 - padding.bottom - (main.y + main.height as i32),
+                ),
+                Edge::Top | Edge::Bottom => (
+                    main.x - padding.left,
+                    width as i32 - padding.right - (main.x + main.width as i32),
+                ),
+            };
+            assert!(before > 0, "{main:?}");
+            assert!((before - after).abs() <= 1, "{before} then {after}");
+        }
+    }
+}
+
+#[test]
+fn min_view_size_floors_the_secondary_windows() {
+    let config = || Config {
+        secondary_window_size: 0.1,
+        min_view_size: 250,
+        ..Config::default()
+    };
+    let views = layout(&mut Carousel::new(config()), 4, 801, 601).views;
+    assert_eq!(
+        rects(&views),
+        [
+            (6, 6, 469, 589),
+            (481, 6, 314, 250),
+            (481, 262, 314, 250),
+            (481, 518, 314, 250),
+        ],
+    );
+    assert!(views[1..].iter().all(|view| view.height >= 250));
+
+    // Without the floor, the windows are a tenth of the secondary area.
+    let unfloored = Config {
+        min_view_size: 0,
+        ..config()
+    };
+    let views = layout(&mut Carousel::new(unfloored), 4, 801, 601).views;
+    assert_eq!(rects(&views[1..2]), [(481, 6, 314, 53)]);
+
+    // A secondary area too small for one window falls back to the same size.
+    let views = layout(&mut Carousel::new(config()), 4, 801, 200).views;
+    assert_eq!(rects(&views[1..2]), [(481, 6, 314, 13)]);
+}
+
+#[test]
+fn min_view_size_counts_the_windows_shown() {
+    let mut carousel = Carousel::new(Config {
+        secondary_window_size: 0.1,
+        min_view_size: 250,
+        clamp_scroll: true,
+        ..Config::default()
+    });
+    // 589 pixels fit fewer than 3 windows of 250 pixels.
+    layout(&mut carousel, 6, 801, 601);
+    assert_eq!(
+        carousel.describe(),
+        "carousel: main left at 60%, 2 of 5 visible, scroll 0.0",
+    );
+
+    cmd(&mut carousel, "scroll-to 3");
+    assert_eq!(format!("{:.3}", carousel.config.scroll_offset), "2.338");
+
+    cmd(&mut carousel, "scroll 100");
+    layout(&mut carousel, 6, 801, 601);
+    assert_eq!(format!("{:.3}", carousel.config.scroll_offset), "2.676");
+}
+
+#[test]
+fn outer_padding_is_applied_per_edge() {
+    let symmetric = Config {
+        outer_padding: Padding::uniform(6),
+        ..Config::default()
+    };
+    assert_eq!(
+        rects(&layout(&mut Carousel::new(symmetric), 3, 1920, 1080).views),
+        [
+            (6, 6, 1141, 1068),
+            (1153, 6, 761, 531),
+            (1153, 543, 761, 531),
+        ],
+    );
+
+    let status_bar = Config {
+        outer_padding: Padding {
+            top: 30,
+            right: 10,
+            bottom: 0,
+            left: 20,
+        },
+        ..Config::default()
+    };
+    assert_eq!(
+        rects(&layout(&mut Carousel::new(status_bar), 3, 1920, 1080).views),
+        [
+            (20, 30, 1130, 1050),
+            (1156, 30, 754, 522),
+            (1156, 558, 754, 522),
+        ],
+    );
+}
+
+#[test]
+fn scaled_gaps_keep_layouts_alike_across_resolutions() {
+    // Paddings of a hundredth of the output's smaller dimension.
+    let config = || Config {
+        outer_padding: 10.into(),
+        view_padding: 10,
+        gaps_scale_with_output: true,
+        ..Config::default()
+    };
+    let full_hd = rects(&layout(&mut Carousel::new(config()), 3, 1920, 1080).views);
+    assert_eq!(
+        full_hd,
+        [
+            (11, 11, 1132, 1058),
+            (1154, 11, 755, 523),
+            (1154, 545, 755, 523),
+        ],
+    );
+    let ultra_hd = rects(&layout(&mut Carousel::new(config()), 3, 3840, 2160).views);
+    assert_eq!(
+        ultra_hd,
+        [
+            (22, 22, 2264, 2116),
+            (2308, 22, 1510, 1047),
+            (2308, 1091, 1510, 1047),
+        ],
+    );
+
+    // The output's scale makes no difference to a fraction of it.
+    let mut scaled = Carousel::new(Config {
+        logical_padding: true,
+        ..config()
+    });
+    cmd(&mut scaled, "set-output-scale 2");
+    assert_eq!(rects(&layout(&mut scaled, 3, 1920, 1080).views), full_hd);
+
+    // Absolute paddings stay the same size on the larger output instead.
+    let absolute = rects(&layout(&mut Carousel::new(Config::default()), 3, 3840, 2160).views);
+    assert_eq!(absolute[0], (6, 6, 2293, 2148));
+}
@@ -0,0 +1,135 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+fn once(args: &[&str], input: &str) -> (bool, Value) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_river-carousel-layout"))
+        .arg("--once")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or(Value::Null);
+    (output.status.success(), json)
+}
+
+fn view(x: i32, y: i32, width: u32, height: u32) -> Value {
+    json!({"x": x, "y": y, "width": width, "height": height})
+}
+
+#[test]
+fn once_prints_a_single_layout() {
+    assert_eq!(
+        once(&[], "3 1920 1080 1 DP-1\n"),
+        (
+            true,
+            json!({
+                "layout_name": "carousel: left @0.0",
+                "views": [
+                    view(6, 6, 1141, 1068),
+                    view(1153, 6, 761, 531),
+                    view(1153, 543, 761, 531),
+                ],
+            }),
+        ),
+    );
+}
+
+#[test]
+fn once_applies_the_arguments() {
+    let (success, layout) = once(&["--main-location", "right"], "1 1920 1080 1 DP-1");
+    assert!(success);
+    assert_eq!(layout["views"], json!([view(773, 6, 1141, 1068)]));
+    assert_eq!(layout["layout_name"], "carousel: right @0.0");
+}
+
+#[test]
+fn once_fails_on_missing_parameters() {
+    assert_eq!(once(&[], "3 1920 1080 1"), (false, Value::Null));
+}
+
+#[test]
+fn debug_logging_stays_off_stdout() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_river-carousel-layout"))
+        .arg("--once")
+        .env("RUST_LOG", "debug")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"3 1920 1080 1 DP-1")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // The layout is logged, but only the JSON is printed.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("carousel: left @0.0"), "{stderr}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "{stdout}");
+    let layout: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(layout["views"][0], view(6, 6, 1141, 1068));
+}
+
+#[test]
+fn arguments_configure_the_layout() {
+    let args = [
+        "--main-location",
+        "top",
+        "--main-ratio",
+        "0.5",
+        "--secondary-size",
+        "0.25",
+        "--outer-padding",
+        "10",
+        "--view-padding",
+        "-4",
+    ];
+    let (success, layout) = once(&args, "3 1920 1080 1 DP-1");
+    assert!(success);
+    assert_eq!(
+        layout["views"],
+        json!([
+            view(10, 10, 1900, 530),
+            view(10, 540, 478, 530),
+            view(484, 540, 478, 530),
+        ]),
+    );
+}
+
+#[test]
+fn invalid_arguments_exit_with_a_message() {
+    let output = Command::new(env!("CARGO_BIN_EXE_river-carousel-layout"))
+        .args(["--once", "--main-ratio", "2"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid configuration"), "{stderr}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_river-carousel-layout"))
+        .args(["--once", "--main-location", "middle"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--main-location"), "{stderr}");
+}
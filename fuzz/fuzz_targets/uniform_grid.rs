@@ -0,0 +1,43 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use river_uniform_grid_layout::{Config, UniformGrid};
+use river_layout_toolkit::Layout;
+
+#[derive(Arbitrary, Debug)]
+enum Action {
+    Command(String),
+    Generate {
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+    },
+}
+
+fuzz_target!(|input: (Config, Vec<Action>)| {
+    let (config, actions) = input;
+    let mut layout = UniformGrid::new(config);
+    for action in actions {
+        match action {
+            Action::Command(cmd) => {
+                let _ = layout.user_cmd(cmd, None, "fuzz");
+            }
+            Action::Generate {
+                view_count,
+                usable_width,
+                usable_height,
+                tags,
+            } => {
+                let _ = layout.generate_layout(
+                    view_count,
+                    usable_width,
+                    usable_height,
+                    tags,
+                    "fuzz",
+                );
+            }
+        }
+    }
+});
@@ -0,0 +1,50 @@
+//! The `--once` mode shared by the layouts' binaries, included by each of them
+//! as a module.
+
+use std::io::Read;
+
+use anyhow::Context;
+use river_layout_toolkit::Layout;
+use serde_json::json;
+
+/// Generates a single layout from the parameters on standard input, and prints
+/// it as JSON.
+pub fn once<L>(mut layout: L) -> anyhow::Result<()>
+where
+    L: Layout,
+    L::Error: Send + Sync + 'static,
+{
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let mut params = input.split_whitespace();
+    let mut param = |name: &str| params.next().with_context(|| format!("missing {name}"));
+
+    let view_count = param("view_count")?.parse().context("invalid view_count")?;
+    let width = param("width")?.parse().context("invalid width")?;
+    let height = param("height")?.parse().context("invalid height")?;
+    let tags = param("tags")?.parse().context("invalid tags")?;
+    let output = param("output")?.to_owned();
+
+    let generated = layout.generate_layout(view_count, width, height, tags, &output)?;
+    let views: Vec<_> = generated
+        .views
+        .iter()
+        .map(|view| {
+            json!({
+                "x": view.x,
+                "y": view.y,
+                "width": view.width,
+                "height": view.height,
+            })
+        })
+        .collect();
+
+    println!(
+        "{}",
+        json!({
+            "layout_name": generated.layout_name,
+            "views": views,
+        })
+    );
+    Ok(())
+}
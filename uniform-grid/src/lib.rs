@@ -1,17 +1,313 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
 use glam::{IVec2, Vec2};
-use log::error;
+use log::{error, info, warn};
 use river_layout_toolkit::{GeneratedLayout, Layout, Rectangle};
 
+/// An edge of the layout area.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Which row the first views are placed in.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum RowOrder {
+    /// Fill the grid starting from the top row.
+    TopDown,
+
+    /// Fill the grid starting from the bottom row.
+    BottomUp,
+}
+
+/// The order that views fill the cells of the grid in.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum FillOrder {
+    /// Fill each row in turn, alternating direction so that consecutive views
+    /// are always next to each other.
+    Snake,
+
+    /// Fill each row in turn from left to right.
+    RowMajor,
+
+    /// Fill each column in turn from top to bottom, starting with the left
+    /// column.
+    ColumnMajor,
+}
+
+/// Formats a fill order as its name in `set-fill-order`.
+impl fmt::Display for FillOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FillOrder::Snake => "snake",
+            FillOrder::RowMajor => "row-major",
+            FillOrder::ColumnMajor => "column-major",
+        })
+    }
+}
+
+/// Where to place the views that don't fill the last row of the grid.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Overflow {
+    /// Leave them in a partially filled last row.
+    LastRow,
+
+    /// Move them to an extra column on the right, dividing its height evenly
+    /// between them.
+    Sidebar,
+}
+
+/// What the grid is chosen to fit.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Fit {
+    /// Make each cell as close as possible to the target aspect ratio.
+    CellAspect,
+
+    /// Make the grid's shape, in rows and columns, as close as possible to the
+    /// output's aspect ratio, ignoring the target aspect ratio.
+    ///
+    /// This favors cells close to square, and lays out views in the same
+    /// proportions as the output itself.
+    GridAspect,
+}
+
+/// A part of the layout area, as fractions of its size.
+#[derive(Debug)]
+pub struct Region {
+    /// Top-left corner of the region.
+    pub position: Vec2,
+
+    /// Size of the region.
+    pub size: Vec2,
+}
+
+/// Target aspect ratios to use depending on the orientation of the output.
+#[derive(Debug)]
+pub struct AspectProfile {
+    /// Target aspect ratio for outputs taller than they are wide.
+    pub portrait: f32,
+
+    /// Target aspect ratio for all other outputs.
+    pub landscape: f32,
+}
+
+#[derive(Debug)]
 #[non_exhaustive]
 pub struct Config {
     /// The aspect ratio to approximate with every grid extension.
-    target_aspect: f32,
+    pub target_aspect: f32,
 
     /// Padding around the edge of the layout area, in pixels.
     pub outer_padding: i32,
 
     /// Padding between views, in pixels.
     pub view_padding: i32,
+
+    /// Prefer grids with exactly as many cells as there are views.
+    ///
+    /// When enabled, a grid whose cell count matches the view count is chosen
+    /// over a larger one, as long as its cells are still a reasonable fit for
+    /// the target aspect ratio. (Prime view counts, for example, will usually
+    /// still fall back to a larger grid.)
+    pub prefer_exact: bool,
+
+    /// Sizes of groups of consecutive views to pack together.
+    ///
+    /// Adjacent views in the same group have no padding between them, so each
+    /// group appears as a cluster, separated from the others by
+    /// `view_padding`. Views beyond the listed groups are not grouped.
+    pub groups: Vec<u32>,
+
+    /// Where to place the grid within the layout area when it doesn't fill it
+    /// completely, as a fraction of the leftover space on each axis.
+    ///
+    /// `(0.0, 0.0)` places the grid in the top-left corner, `(0.5, 0.5)`
+    /// centers it, and `(1.0, 1.0)` places it in the bottom-right corner.
+    pub grid_anchor: Vec2,
+
+    /// Letterbox views to their preferred aspect ratio, as set by the
+    /// `set-view-aspect` command.
+    ///
+    /// Each such view is shrunk to its aspect ratio and centered within the
+    /// space it would otherwise fill.
+    pub respect_client_aspect: bool,
+
+    /// Keep the column count fixed, and only add rows as views are added.
+    ///
+    /// This keeps existing views from moving sideways when new ones appear.
+    /// The column count is locked in by the first layout with any views, or
+    /// by the `lock-columns` command, and is released once all views are
+    /// closed.
+    pub grow_downward: bool,
+
+    /// Vertical offset of the grid, in "number of rows".
+    ///
+    /// Positive values scroll the grid up, and negative values leave empty
+    /// space above the first row.
+    pub scroll_offset: f32,
+
+    /// Use the grid with the fewest cells that still fits every view.
+    ///
+    /// Like `prefer_exact`, this only considers grids whose cells are a
+    /// reasonable fit for the target aspect ratio. The fraction of unused
+    /// cells is logged after each layout.
+    pub compact: bool,
+
+    /// Which row the first views are placed in.
+    pub row_order: RowOrder,
+
+    /// Snap the edges of every view to multiples of this many pixels.
+    ///
+    /// Layout coordinates are relative to the output, so this lines views up
+    /// across outputs whose own positions are multiples of the same step,
+    /// reducing visible jumps when windows move between them.
+    pub global_grid_step: Option<i32>,
+
+    /// Use a different target aspect ratio depending on the orientation of
+    /// the output, instead of `target_aspect`.
+    pub aspect_profile: Option<AspectProfile>,
+
+    /// The most times per second to step layout animations, or `None` to step
+    /// them on every layout request.
+    pub max_fps: Option<u32>,
+
+    /// Space to leave empty at the top of each cell, in pixels.
+    ///
+    /// Unlike `view_padding`, which separates views, this insets each view
+    /// within its own cell, leaving room that looks like a title bar for
+    /// windows without server-side decorations.
+    pub decoration_inset: i32,
+
+    /// Where to place the views that don't fill the last row of the grid.
+    pub overflow: Overflow,
+
+    /// How much to favor cells wider than the target aspect ratio over taller
+    /// ones, between -1 and 1.
+    ///
+    /// Positive values make wider cells score as if they were closer to the
+    /// target than equally mismatched taller cells, so that near-ties resolve
+    /// toward wider cells. Negative values favor taller cells instead.
+    pub aspect_bias: f32,
+
+    /// Confine the grid to this part of the layout area, leaving the rest
+    /// empty.
+    ///
+    /// This is useful for keeping room for a floating window, such as a
+    /// picture-in-picture player. Paddings apply within the region.
+    pub region: Option<Region>,
+
+    /// What the grid is chosen to fit.
+    pub fit: Fit,
+
+    /// Short names for commands, for less verbose keybindings.
+    ///
+    /// When a command starts with one of these names, the name is replaced
+    /// with the command it stands for, and any further arguments are appended
+    /// to it. More aliases can be added with the `alias` command.
+    pub aliases: HashMap<String, String>,
+
+    /// Give every cell this exact size, in pixels, instead of choosing a grid
+    /// for the target aspect ratio.
+    ///
+    /// The grid is made of as many whole cells as fit in the layout area,
+    /// placed according to `grid_anchor`. Views that don't fit continue in
+    /// extra rows below it, which can be scrolled into view with
+    /// `scroll_offset`.
+    pub cell_size: Option<IVec2>,
+
+    /// Treat paddings as logical pixels, scaling them by the scale of each
+    /// output, as set by the `set-output-scale` command.
+    pub logical_padding: bool,
+
+    /// Only lay out the views on the currently active tags, hiding the rest.
+    ///
+    /// This relies on the tags of each view being set with the
+    /// `set-view-tags` command. Views whose tags aren't known are always
+    /// shown.
+    pub tag_filter: bool,
+
+    /// Extra space between the first and second rows, in pixels, on top of
+    /// `view_padding`.
+    ///
+    /// This sets the first row apart as a header.
+    pub header_gap: i32,
+
+    /// Leave this cell of the grid empty, placing views around it, as
+    /// `(column, row)`.
+    ///
+    /// This keeps room for a floating widget that should stay in the same
+    /// place as the grid changes. Negative coordinates count from the right
+    /// and bottom of the grid, so `(-1, -1)` is always the bottom-right cell.
+    pub reserve_cell: Option<IVec2>,
+
+    /// Always use a grid of this many columns and rows, as for a video wall.
+    ///
+    /// This replaces all of the ways the grid otherwise adapts to the views:
+    /// cells beyond the last view stay empty, `overflow` and `grow_downward`
+    /// don't apply, and views that don't fit in the grid are collapsed to
+    /// zero size.
+    pub wall: Option<IVec2>,
+
+    /// Move views to their new cells over this many layouts when the grid's
+    /// dimensions change, instead of all at once.
+    ///
+    /// river only asks for a new layout when something changes, so the
+    /// intermediate frames only play out when layouts are generated one after
+    /// another, as by a compositor embedding the layout while
+    /// `UniformGrid::needs_redraw` returns `true`. `0` disables the animation.
+    pub transition_frames: u32,
+
+    /// Always use a grid of this many columns, with as many rows as it takes
+    /// to fit all views, instead of searching for the one that best fits the
+    /// target aspect ratio.
+    pub columns: Option<u32>,
+
+    /// Center the views of a partially filled last row, instead of leaving
+    /// them at whichever end of the row the fill order reaches first.
+    ///
+    /// This doesn't apply while the reserved cell is in the last row, nor
+    /// with `FillOrder::ColumnMajor`, which leaves the last column partially
+    /// filled instead.
+    pub center_last_row: bool,
+
+    /// The order that views fill the cells of the grid in.
+    ///
+    /// `row_order` still decides whether the first row is the top or the
+    /// bottom one.
+    pub fill_order: FillOrder,
+
+    /// Use the aspect ratio of the usable area of each output as the target
+    /// aspect ratio, instead of `target_aspect` or `aspect_profile`.
+    pub match_output_aspect: bool,
+
+    /// Give the first view this fraction of the layout area, along
+    /// `master_location`, and lay out the rest of the views in a grid in the
+    /// remaining area.
+    ///
+    /// A single view still fills the whole layout area.
+    pub master_ratio: Option<f32>,
+
+    /// The edge that the master area of `master_ratio` extends out from.
+    pub master_location: Edge,
+
+    /// Break ties between equally fitting grids by this seed, before the
+    /// usual preference for fewer columns (or for the extra column, while
+    /// growing the grid).
+    ///
+    /// Ties are broken by integer hashing of the seed and each grid's size, so
+    /// the same seed picks the same grids on every machine.
+    pub tie_break_seed: Option<u64>,
 }
 
 impl Default for Config {
@@ -20,10 +316,334 @@ impl Default for Config {
             target_aspect: 16.0 / 9.0,
             outer_padding: 6,
             view_padding: 6,
+            prefer_exact: false,
+            groups: Vec::new(),
+            grid_anchor: Vec2::ZERO,
+            respect_client_aspect: false,
+            grow_downward: false,
+            scroll_offset: 0.0,
+            compact: false,
+            row_order: RowOrder::TopDown,
+            global_grid_step: None,
+            aspect_profile: None,
+            max_fps: None,
+            decoration_inset: 0,
+            overflow: Overflow::LastRow,
+            aspect_bias: 0.0,
+            region: None,
+            fit: Fit::CellAspect,
+            aliases: HashMap::new(),
+            cell_size: None,
+            logical_padding: false,
+            tag_filter: false,
+            header_gap: 0,
+            reserve_cell: None,
+            wall: None,
+            transition_frames: 0,
+            columns: None,
+            center_last_row: false,
+            fill_order: FillOrder::Snake,
+            match_output_aspect: false,
+            master_ratio: None,
+            master_location: Edge::Left,
+            tie_break_seed: None,
+        }
+    }
+}
+
+impl Config {
+    /// A configuration matching rivertile's default padding, as a starting
+    /// point when migrating from it.
+    ///
+    /// rivertile applies its view padding on every side of every view, on top
+    /// of the outer padding, so both the gaps between views and the gaps at the
+    /// edge of the output are twice its `view-padding` of 6.
+    pub fn rivertile() -> Self {
+        Self {
+            outer_padding: 12,
+            view_padding: 12,
+            ..Default::default()
+        }
+    }
+
+    /// The aspect ratio to approximate on an output of the given size.
+    fn target_aspect_for(&self, output_size: IVec2) -> f32 {
+        if self.match_output_aspect && output_size.cmpgt(IVec2::ZERO).all() {
+            return output_size.x as f32 / output_size.y as f32;
+        }
+        match &self.aspect_profile {
+            Some(profile) if output_size.y > output_size.x => profile.portrait,
+            Some(profile) => profile.landscape,
+            None => self.target_aspect,
+        }
+    }
+}
+
+/// Floats are hashed by their bit patterns, so that every configuration,
+/// including ones containing NaN, hashes consistently.
+impl Hash for Config {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            target_aspect,
+            outer_padding,
+            view_padding,
+            prefer_exact,
+            groups,
+            grid_anchor,
+            respect_client_aspect,
+            grow_downward,
+            scroll_offset,
+            compact,
+            row_order,
+            global_grid_step,
+            aspect_profile,
+            decoration_inset,
+            overflow,
+            aspect_bias,
+            region,
+            fit,
+            aliases,
+            cell_size,
+            logical_padding,
+            tag_filter,
+            header_gap,
+            reserve_cell,
+            wall,
+            transition_frames,
+            columns,
+            center_last_row,
+            fill_order,
+            match_output_aspect,
+            master_ratio,
+            master_location,
+            max_fps,
+            tie_break_seed,
+        } = self;
+
+        let vec2_bits = |v: Vec2| v.to_array().map(f32::to_bits);
+
+        target_aspect.to_bits().hash(state);
+        outer_padding.hash(state);
+        view_padding.hash(state);
+        prefer_exact.hash(state);
+        groups.hash(state);
+        vec2_bits(*grid_anchor).hash(state);
+        respect_client_aspect.hash(state);
+        grow_downward.hash(state);
+        scroll_offset.to_bits().hash(state);
+        compact.hash(state);
+        row_order.hash(state);
+        global_grid_step.hash(state);
+        aspect_profile
+            .as_ref()
+            .map(|profile| (profile.portrait.to_bits(), profile.landscape.to_bits()))
+            .hash(state);
+        decoration_inset.hash(state);
+        overflow.hash(state);
+        aspect_bias.to_bits().hash(state);
+        region
+            .as_ref()
+            .map(|region| (vec2_bits(region.position), vec2_bits(region.size)))
+            .hash(state);
+        fit.hash(state);
+        let mut aliases: Vec<_> = aliases.iter().collect();
+        aliases.sort();
+        aliases.hash(state);
+        cell_size.hash(state);
+        logical_padding.hash(state);
+        tag_filter.hash(state);
+        header_gap.hash(state);
+        reserve_cell.hash(state);
+        wall.hash(state);
+        transition_frames.hash(state);
+        columns.hash(state);
+        center_last_row.hash(state);
+        fill_order.hash(state);
+        match_output_aspect.hash(state);
+        master_ratio.map(f32::to_bits).hash(state);
+        master_location.hash(state);
+        max_fps.hash(state);
+        tie_break_seed.hash(state);
+    }
+}
+
+/// Configurations are equal when they hash the same data, so that `Eq` always
+/// agrees with `Hash`.
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        HashedBytes::of(self) == HashedBytes::of(other)
+    }
+}
+
+impl Eq for Config {}
+
+/// A hasher that keeps all of the data written to it, to compare values by
+/// what they hash.
+#[derive(PartialEq, Default)]
+struct HashedBytes(Vec<u8>);
+
+impl HashedBytes {
+    fn of(value: &impl Hash) -> Self {
+        let mut bytes = Self::default();
+        value.hash(&mut bytes);
+        bytes
+    }
+}
+
+impl Hasher for HashedBytes {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// The 64-bit FNV-1a hash of the collected data.
+    fn finish(&self) -> u64 {
+        self.0.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+/// Arbitrary configurations for fuzzing, within the same bounds as the
+/// commands that set them.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Config {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        fn pixels(u: &mut arbitrary::Unstructured) -> arbitrary::Result<i32> {
+            Ok(u.arbitrary::<i16>()?.into())
         }
+        fn size(u: &mut arbitrary::Unstructured) -> arbitrary::Result<i32> {
+            u.int_in_range(0..=i16::MAX.into())
+        }
+        fn cells(u: &mut arbitrary::Unstructured) -> arbitrary::Result<IVec2> {
+            Ok(IVec2::new(
+                u.int_in_range(1..=i16::MAX.into())?,
+                u.int_in_range(1..=i16::MAX.into())?,
+            ))
+        }
+        fn fraction(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(u.int_in_range(0..=u16::MAX)? as f32 / u16::MAX as f32)
+        }
+        fn finite(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(Some(u.arbitrary::<f32>()?)
+                .filter(|value| value.is_finite())
+                .unwrap_or_default())
+        }
+        fn aspect(u: &mut arbitrary::Unstructured) -> arbitrary::Result<f32> {
+            Ok(Some(finite(u)?.abs())
+                .filter(|&aspect| aspect > 0.0)
+                .unwrap_or(1.0))
+        }
+
+        Ok(Self {
+            target_aspect: aspect(u)?,
+            outer_padding: size(u)?,
+            view_padding: pixels(u)?,
+            prefer_exact: u.arbitrary()?,
+            groups: u.arbitrary()?,
+            grid_anchor: Vec2::new(fraction(u)?, fraction(u)?),
+            respect_client_aspect: u.arbitrary()?,
+            grow_downward: u.arbitrary()?,
+            scroll_offset: finite(u)?,
+            compact: u.arbitrary()?,
+            row_order: u.arbitrary()?,
+            global_grid_step: u
+                .arbitrary::<bool>()?
+                .then(|| u.int_in_range(1..=i16::MAX.into()))
+                .transpose()?,
+            aspect_profile: u
+                .arbitrary::<bool>()?
+                .then(|| {
+                    Ok(AspectProfile {
+                        portrait: aspect(u)?,
+                        landscape: aspect(u)?,
+                    })
+                })
+                .transpose()?,
+            max_fps: u
+                .arbitrary::<bool>()?
+                .then(|| u.int_in_range(1..=u32::MAX))
+                .transpose()?,
+            decoration_inset: size(u)?,
+            overflow: u.arbitrary()?,
+            aspect_bias: fraction(u)? * 2.0 - 1.0,
+            region: u
+                .arbitrary::<bool>()?
+                .then(|| {
+                    let position = Vec2::new(fraction(u)?, fraction(u)?);
+                    let size = Vec2::new(fraction(u)?, fraction(u)?);
+                    Ok(Region {
+                        position,
+                        size: size.min(Vec2::ONE - position),
+                    })
+                })
+                .transpose()?,
+            fit: u.arbitrary()?,
+            aliases: u.arbitrary()?,
+            cell_size: u.arbitrary::<bool>()?.then(|| cells(u)).transpose()?,
+            logical_padding: u.arbitrary()?,
+            tag_filter: u.arbitrary()?,
+            header_gap: pixels(u)?,
+            reserve_cell: u
+                .arbitrary::<bool>()?
+                .then(|| Ok(IVec2::new(pixels(u)?, pixels(u)?)))
+                .transpose()?,
+            wall: u.arbitrary::<bool>()?.then(|| cells(u)).transpose()?,
+            transition_frames: u.arbitrary()?,
+            columns: u
+                .arbitrary::<bool>()?
+                .then(|| u.int_in_range(1..=i16::MAX as u32))
+                .transpose()?,
+            center_last_row: u.arbitrary()?,
+            fill_order: u.arbitrary()?,
+            match_output_aspect: u.arbitrary()?,
+            master_ratio: u
+                .arbitrary::<bool>()?
+                .then(|| Ok(u.int_in_range(1..=u16::MAX)? as f32 / (u16::MAX as f32 + 1.0)))
+                .transpose()?,
+            master_location: u.arbitrary()?,
+            tie_break_seed: u.arbitrary()?,
+        })
     }
 }
 
+/// Entries of a map of floats, in a stable order and with the floats as bits,
+/// to be hashed.
+fn sorted_bits<K: Ord + Clone>(map: &HashMap<K, f32>) -> Vec<(K, u32)> {
+    let mut entries: Vec<_> = map
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_bits()))
+        .collect();
+    entries.sort_unstable();
+    entries
+}
+
+/// The worst efficiency a grid may have to be picked by `prefer_exact` or
+/// `compact`, before falling back to the default search.
+const MAX_FIT_EFFICIENCY: f32 = 2.0;
+
+/// Steps per unit of efficiency in `Grid::rank`.
+const EFFICIENCY_PRECISION: f32 = 1000000.0;
+
+/// Efficiency of grids whose views, or the output itself, have no area, so
+/// that they rank below every other grid.
+const DEGENERATE_EFFICIENCY: f32 = f32::INFINITY;
+
+/// Extra space left around each view by `show-grid-lines`, in pixels.
+const GRID_LINE_WIDTH: u32 = 2;
+
+/// Largest usable width or height that is laid out; any larger area is laid
+/// out as if it were this size.
+///
+/// No output comes anywhere close, and this leaves the layout arithmetic
+/// plenty of headroom before it could overflow an `i32`.
+const MAX_USABLE_SIZE: u32 = u16::MAX as u32;
+
+/// Largest number of views that is laid out; layouts of any more are refused.
+///
+/// Like `MAX_USABLE_SIZE`, this is far beyond anything river asks for, and
+/// keeps every candidate grid's cell count well within an `i32`.
+const MAX_VIEW_COUNT: u32 = u16::MAX as u32;
+
 #[derive(Clone, Copy)]
 struct Grid {
     size: IVec2,
@@ -34,17 +654,202 @@ impl Grid {
         self.size.x * self.size.y
     }
 
+    /// Fraction of the cells left empty when the given number are used.
+    fn unused_fraction(&self, used_cells: u32) -> f32 {
+        let cells = self.total_cells();
+        (cells - used_cells as i32) as f32 / cells as f32
+    }
+
+    fn efficiency(&self, config: &Config, output_size: IVec2) -> f32 {
+        match config.fit {
+            Fit::CellAspect => self
+                .layout(config, output_size)
+                .efficiency(config.target_aspect_for(output_size), config.aspect_bias),
+            Fit::GridAspect if output_size.cmple(IVec2::ZERO).any() => DEGENERATE_EFFICIENCY,
+            Fit::GridAspect => {
+                let grid_aspect = self.size.x as f32 / self.size.y as f32;
+                let output_aspect = output_size.x as f32 / output_size.y as f32;
+                aspect_mismatch(grid_aspect, output_aspect, config.aspect_bias)
+            }
+        }
+    }
+
+    /// Efficiency quantized to an integer, for ranking grids by it, lower
+    /// being better.
+    ///
+    /// Comparing integers rather than floats makes it explicit which grids
+    /// tie, for the callers to break ties in a defined order. Grids whose
+    /// efficiency isn't finite, such as degenerate grids, rank after every
+    /// other grid, however poorly those fit.
+    fn rank(&self, config: &Config, output_size: IVec2) -> (bool, i32) {
+        let efficiency = self.efficiency(config, output_size);
+        if efficiency.is_finite() {
+            (false, (efficiency * EFFICIENCY_PRECISION) as i32)
+        } else {
+            (true, i32::MAX)
+        }
+    }
+
+    /// Key that orders grids of the same rank by `Config::tie_break_seed`, or
+    /// leaves them tied without one.
+    fn tie_break(&self, config: &Config) -> u64 {
+        config.tie_break_seed.map_or(0, |seed| {
+            let size = (self.size.x as u32 as u64) << 32 | self.size.y as u32 as u64;
+            mix(seed ^ mix(size))
+        })
+    }
+
+    /// The smallest grid with the given number of columns that fits all views.
+    fn with_columns(columns: i32, view_count: u32) -> Self {
+        let rows = (view_count as i32 + columns - 1) / columns;
+        Grid {
+            size: IVec2::new(columns, rows.max(1)),
+        }
+    }
+
+    /// The grid of as many cells of the given size as fit in the layout area,
+    /// extended by as many rows as it takes to fit all views.
+    fn with_cell_size(
+        config: &Config,
+        cell_size: IVec2,
+        view_count: u32,
+        output_size: IVec2,
+    ) -> Self {
+        let padded_size = output_size - 2 * IVec2::splat(config.outer_padding);
+        let stride = cell_size
+            .saturating_add(IVec2::splat(config.view_padding))
+            .max(IVec2::ONE);
+        // Anything beyond an `i16` of cells is surely a mistake, and could
+        // overflow the count of cells.
+        let fitting = (padded_size.saturating_add(IVec2::splat(config.view_padding)) / stride)
+            .clamp(IVec2::ONE, IVec2::splat(i16::MAX.into()));
+
+        let grid = Self::with_columns(fitting.x, view_count);
+        Grid {
+            size: IVec2::new(fitting.x, grid.size.y.max(fitting.y)),
+        }
+    }
+
+    /// Finds the grid whose cells best approximate the target aspect ratio.
+    fn choose(config: &Config, view_count: u32, output_size: IVec2) -> Self {
+        if let Some(columns) = config.columns {
+            return Self::with_columns(columns as i32, view_count);
+        }
+        if let Some(cell_size) = config.cell_size {
+            return Self::with_cell_size(config, cell_size, view_count, output_size);
+        }
+
+        let mut grid = Grid { size: IVec2::ONE };
+
+        while (grid.total_cells() as u32) < view_count {
+            let options = [
+                Grid {
+                    size: grid.size + IVec2::X,
+                },
+                Grid {
+                    size: grid.size + IVec2::Y,
+                },
+            ];
+            // Ties go to the extra column.
+            grid = options
+                .into_iter()
+                .min_by_key(|grid| {
+                    (
+                        grid.rank(config, output_size),
+                        grid.tie_break(config),
+                        -grid.size.x,
+                    )
+                })
+                .unwrap();
+        }
+
+        if config.compact {
+            if let Some(smallest) = Self::smallest(config, view_count, output_size) {
+                grid = smallest;
+            }
+        } else if config.prefer_exact && grid.total_cells() as u32 != view_count {
+            if let Some(exact) = Self::best_exact(config, view_count, output_size) {
+                grid = exact;
+            }
+        }
+
+        grid
+    }
+
+    /// The smallest grid for each possible column count, whose cells are
+    /// within `MAX_FIT_EFFICIENCY`.
+    fn fitting(
+        config: &Config,
+        view_count: u32,
+        output_size: IVec2,
+    ) -> impl Iterator<Item = Self> + '_ {
+        (1..=view_count as i32)
+            .map(move |columns| Self::with_columns(columns, view_count))
+            .filter(move |grid| grid.efficiency(config, output_size) <= MAX_FIT_EFFICIENCY)
+    }
+
+    /// Finds the best grid with exactly `view_count` cells, if any of them is
+    /// within `MAX_FIT_EFFICIENCY`, breaking ties by fewest columns.
+    fn best_exact(config: &Config, view_count: u32, output_size: IVec2) -> Option<Self> {
+        Self::fitting(config, view_count, output_size)
+            .filter(|grid| grid.total_cells() as u32 == view_count)
+            .min_by_key(|grid| {
+                (
+                    grid.rank(config, output_size),
+                    grid.tie_break(config),
+                    grid.size.x,
+                )
+            })
+    }
+
+    /// Finds the grid with the fewest cells, if any of them is within
+    /// `MAX_FIT_EFFICIENCY`, breaking ties by efficiency and then by fewest
+    /// columns.
+    fn smallest(config: &Config, view_count: u32, output_size: IVec2) -> Option<Self> {
+        Self::fitting(config, view_count, output_size).min_by_key(|grid| {
+            (
+                grid.total_cells(),
+                grid.rank(config, output_size),
+                grid.tie_break(config),
+                grid.size.x,
+            )
+        })
+    }
+
     fn layout(&self, config: &Config, output_size: IVec2) -> GridLayout {
         let offset = IVec2::splat(config.outer_padding).as_vec2();
         let padded_size = output_size.as_vec2() - 2.0 * offset;
 
+        // The header gap only exists below the first row, if there's more
+        // than one.
+        let header_gap = if self.size.y > 1 {
+            config.header_gap
+        } else {
+            0
+        };
+        let header_gap = Vec2::Y * header_gap as f32;
+
         let view_padding = IVec2::splat(config.view_padding);
-        let stride = (padded_size + view_padding.as_vec2()) / self.size.as_vec2();
-        let view_size = stride.as_ivec2() - IVec2::splat(config.view_padding);
+        let (stride, view_size) = match config.cell_size {
+            Some(cell_size) => ((cell_size + view_padding).as_vec2(), cell_size),
+            None => {
+                let stride =
+                    (padded_size - header_gap + view_padding.as_vec2()) / self.size.as_vec2();
+                (stride, stride.as_ivec2() - view_padding)
+            }
+        };
+
+        let grid_size =
+            stride * (self.size - IVec2::ONE).as_vec2() + view_size.as_vec2() + header_gap;
+        let leftover = (padded_size - grid_size).max(Vec2::ZERO);
+        let offset =
+            offset + leftover * config.grid_anchor - stride * Vec2::Y * config.scroll_offset;
+
         GridLayout {
             offset,
             stride,
             view_size,
+            header_gap,
         }
     }
 }
@@ -53,6 +858,9 @@ struct GridLayout {
     offset: Vec2,
     stride: Vec2,
     view_size: IVec2,
+
+    /// Extra space between the first and second rows.
+    header_gap: Vec2,
 }
 
 impl GridLayout {
@@ -61,23 +869,230 @@ impl GridLayout {
     }
 
     /// Fraction of the view area that the target aspect ratio would fill.
-    fn efficiency(&self, target_aspect: f32) -> f32 {
-        let arr = self.aspect_ratio() / target_aspect;
-        if arr > 1.0 {
-            arr
-        } else {
-            1.0 / arr
+    fn efficiency(&self, target_aspect: f32, bias: f32) -> f32 {
+        if self.view_size.cmple(IVec2::ZERO).any() {
+            return DEGENERATE_EFFICIENCY;
         }
+        aspect_mismatch(self.aspect_ratio(), target_aspect, bias)
+    }
+
+    /// The top-left corner of the view at the given grid position.
+    ///
+    /// Both grid positions and layout coordinates follow river's convention,
+    /// starting from the top-left corner with y increasing downward, so row 0
+    /// is the top row.
+    fn position(&self, grid_position: IVec2) -> IVec2 {
+        let header_gap = if grid_position.y > 0 {
+            self.header_gap
+        } else {
+            Vec2::ZERO
+        };
+        (self.offset + self.stride * grid_position.as_vec2() + header_gap).as_ivec2()
     }
 
-    fn at(&self, grid_position: IVec2) -> Rectangle {
-        let position = (self.offset + self.stride * grid_position.as_vec2()).as_ivec2();
+    /// The view at the given grid position.
+    ///
+    /// The padding toward each neighboring cell in `joined` is closed, meeting
+    /// the neighbor's view halfway.
+    fn at(&self, grid_position: IVec2, joined: Joined) -> Rectangle {
+        // Views scrolled far enough away saturate at the edge of the
+        // coordinate space rather than overflowing.
+        let min = self.position(grid_position);
+        let max = min.saturating_add(self.view_size);
+        let prev_max = self
+            .position(grid_position - IVec2::ONE)
+            .saturating_add(self.view_size);
+        let next_min = self.position(grid_position + IVec2::ONE);
+        let midpoint = |a: i32, b: i32| ((a as i64 + b as i64) / 2) as i32;
+
+        let mut new_min = min;
+        let mut new_max = max;
+        if joined.left {
+            new_min.x = midpoint(prev_max.x, min.x);
+        }
+        if joined.right {
+            new_max.x = midpoint(max.x, next_min.x);
+        }
+        if joined.top {
+            new_min.y = midpoint(prev_max.y, min.y);
+        }
+        if joined.bottom {
+            new_max.y = midpoint(max.y, next_min.y);
+        }
+
+        // Cells collapse when the layout area is too small for its padding.
+        let size = new_max.saturating_sub(new_min).max(IVec2::ZERO);
         Rectangle {
-            x: position.x,
-            y: position.y,
-            width: self.view_size.x.try_into().unwrap(),
-            height: self.view_size.y.try_into().unwrap(),
+            x: new_min.x,
+            y: new_min.y,
+            width: size.x as u32,
+            height: size.y as u32,
+        }
+    }
+}
+
+/// How far `aspect` is from `target_aspect`, as the ratio of the larger to the
+/// smaller, so 1.0 is a perfect match.
+///
+/// `bias` scales down the mismatch of wider shapes (or of taller shapes, if
+/// negative) exponentially; see `Config::aspect_bias`.
+fn aspect_mismatch(aspect: f32, target_aspect: f32, bias: f32) -> f32 {
+    let arr = aspect / target_aspect;
+    if arr > 1.0 {
+        arr.powf(1.0 - bias)
+    } else {
+        (1.0 / arr).powf(1.0 + bias)
+    }
+}
+
+/// Mixes the bits of `value` thoroughly, the same way on every platform.
+///
+/// This is the finalizer of SplitMix64.
+fn mix(value: u64) -> u64 {
+    let value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/// Which neighbors of a cell belong to the same group.
+#[derive(Clone, Copy, Default)]
+struct Joined {
+    left: bool,
+    right: bool,
+    top: bool,
+    bottom: bool,
+}
+
+/// Moves each edge of `rect` to the nearest multiple of `step`.
+fn snap(rect: &Rectangle, step: i32) -> Rectangle {
+    let snap = |value: i32| {
+        let step = i64::from(step);
+        let snapped = (i64::from(value) + step / 2).div_euclid(step) * step;
+        snapped.clamp(i32::MIN.into(), i32::MAX.into()) as i32
+    };
+    let min = IVec2::new(snap(rect.x), snap(rect.y));
+    let max = IVec2::new(
+        snap(rect.x.saturating_add(rect.width as i32)),
+        snap(rect.y.saturating_add(rect.height as i32)),
+    );
+    let size = max.saturating_sub(min).max(IVec2::ZERO);
+
+    Rectangle {
+        x: min.x,
+        y: min.y,
+        width: size.x as u32,
+        height: size.y as u32,
+    }
+}
+
+/// Shrinks `rect` by `amount` pixels on every side.
+fn inset(rect: &Rectangle, amount: u32) -> Rectangle {
+    let width = rect.width.saturating_sub(2 * amount);
+    let height = rect.height.saturating_sub(2 * amount);
+
+    Rectangle {
+        x: rect.x + ((rect.width - width) / 2) as i32,
+        y: rect.y + ((rect.height - height) / 2) as i32,
+        width,
+        height,
+    }
+}
+
+/// Assigns each view a group ID according to `Config::groups`.
+fn group_ids(groups: &[u32], view_count: usize) -> Vec<usize> {
+    groups
+        .iter()
+        .enumerate()
+        .flat_map(|(id, &size)| std::iter::repeat_n(id, size as usize))
+        .chain(groups.len()..)
+        .take(view_count)
+        .collect()
+}
+
+/// Parses a percentage between 0 and 100 (with or without a trailing `%`)
+/// into a fraction.
+fn parse_percent(arg: Option<&str>, name: &'static str) -> Result<f32, Error> {
+    let arg = arg.ok_or(Error::MissingArgument(name))?;
+    let percent: f32 = arg
+        .strip_suffix('%')
+        .unwrap_or(arg)
+        .parse()
+        .map_err(|_| Error::InvalidArgument(name))?;
+
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(Error::InvalidArgument(name));
+    }
+    Ok(percent / 100.0)
+}
+
+/// Parses an aspect ratio, either as `<width>:<height>` or as a single number.
+fn parse_aspect(arg: &str) -> Option<f32> {
+    let aspect = match arg.split_once(':') {
+        Some((width, height)) => width.parse::<f32>().ok()? / height.parse::<f32>().ok()?,
+        None => arg.parse().ok()?,
+    };
+
+    (aspect.is_finite() && aspect > 0.0).then_some(aspect)
+}
+
+/// The rectangle `progress` of the way from `from` to `to`.
+fn interpolate(from: &Rectangle, to: &Rectangle, progress: f32) -> Rectangle {
+    let lerp = |from: f32, to: f32| from + (to - from) * progress;
+
+    Rectangle {
+        x: lerp(from.x as f32, to.x as f32).round() as i32,
+        y: lerp(from.y as f32, to.y as f32).round() as i32,
+        width: lerp(from.width as f32, to.width as f32).round() as u32,
+        height: lerp(from.height as f32, to.height as f32).round() as u32,
+    }
+}
+
+/// Shrinks `rect` to the given aspect ratio, centered within the original.
+fn letterbox(rect: &Rectangle, aspect: f32) -> Rectangle {
+    let (width, height) = if rect.width as f32 > rect.height as f32 * aspect {
+        ((rect.height as f32 * aspect) as u32, rect.height)
+    } else {
+        (rect.width, (rect.width as f32 / aspect) as u32)
+    };
+
+    Rectangle {
+        x: rect.x + (rect.width.saturating_sub(width) / 2) as i32,
+        y: rect.y + (rect.height.saturating_sub(height) / 2) as i32,
+        width,
+        height,
+    }
+}
+
+/// Limits how often an animation is stepped, following `Config::max_fps`.
+///
+/// Layout requests can arrive much faster than the frames are worth showing,
+/// so a step is only due once a whole frame interval has passed since the last.
+#[derive(Debug, Clone, Default)]
+pub struct FramePacer {
+    interval: Option<Duration>,
+    stepped_at: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(max_fps: Option<u32>) -> Self {
+        Self {
+            interval: max_fps.map(|fps| Duration::from_secs(1) / fps),
+            stepped_at: None,
+        }
+    }
+
+    /// Whether a step is due at `now`, recording it as the latest step if so.
+    pub fn step(&mut self, now: Instant) -> bool {
+        let due = match (self.interval, self.stepped_at) {
+            (Some(interval), Some(stepped_at)) => {
+                now.saturating_duration_since(stepped_at) >= interval
+            }
+            _ => true,
+        };
+        if due {
+            self.stepped_at = Some(now);
         }
+        due
     }
 }
 
@@ -92,15 +1107,466 @@ pub enum Error {
 
     #[error("invalid value for argument {0:?}")]
     InvalidArgument(&'static str),
+
+    #[error("cannot lay out {0} views, at most {MAX_VIEW_COUNT} are supported")]
+    TooManyViews(u32),
+
+    /// A valid command that can't be applied in the current state, which is
+    /// left unchanged.
+    #[error("cannot {operation}: {state}")]
+    State {
+        /// The command that failed.
+        operation: &'static str,
+
+        /// Why the current state doesn't allow it.
+        state: String,
+    },
+}
+
+/// Counters describing the work done by the layout, reported by the `stats`
+/// command.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of layouts generated.
+    pub layouts_generated: u64,
+
+    /// Number of user commands received, including invalid ones.
+    pub commands_received: u64,
+
+    /// Time taken to generate the most recent layout.
+    pub last_generation_time: Duration,
+}
+
+/// Space to leave empty along each edge of the usable area, in pixels.
+#[derive(Default, Hash)]
+struct Insets {
+    top: u32,
+    right: u32,
+    bottom: u32,
+    left: u32,
+}
+
+/// The animation of views from one grid to another, as configured by
+/// `Config::transition_frames`.
+struct Transition {
+    /// Size of the grid being moved to, if it is a grid.
+    grid: Option<IVec2>,
+
+    /// Views as they were when the transition started.
+    from: Vec<Rectangle>,
+
+    /// Views as of the most recent frame.
+    shown: Vec<Rectangle>,
+
+    /// Number of frames shown so far.
+    frame: u32,
+
+    /// Paces the frames by `Config::max_fps` as of when the transition
+    /// started.
+    pacer: FramePacer,
 }
 
+/// The inputs a layout was generated from.
+struct LayoutInputs {
+    view_count: u32,
+    usable_width: u32,
+    usable_height: u32,
+    tags: u32,
+    output: String,
+}
+
+/// The layout generator.
+///
+/// This is normally handed to `river_layout_toolkit::run`, which drives it
+/// from river's layout protocol. It doesn't depend on that event loop,
+/// though, and can be driven directly through the [`Layout`] trait by anything
+/// else that generates layouts, such as a compositor embedding it:
+///
+/// - `user_cmd` applies a command, exactly as if it had been sent with
+///   `riverctl send-layout-cmd`. An invalid command is logged and returned as
+///   an error, without changing any state.
+///
+/// - `generate_layout` returns one view for each of the `view_count` views,
+///   in stack order, in coordinates relative to the top-left corner of the
+///   usable area.
+///
+/// Neither does any I/O other than logging.
+///
+/// ```
+/// use river_layout_toolkit::Layout;
+/// use river_uniform_grid_layout::{UniformGrid, Config};
+///
+/// let mut layout = UniformGrid::new(Config::default());
+/// layout.user_cmd("compact".into(), None, "eDP-1").unwrap();
+/// let generated = layout.generate_layout(3, 1920, 1080, 1, "eDP-1").unwrap();
+/// let views: Vec<_> = generated
+///     .views
+///     .iter()
+///     .map(|view| (view.x, view.y, view.width, view.height))
+///     .collect();
+/// assert_eq!(
+///     views,
+///     [(6, 6, 951, 531), (963, 6, 951, 531), (963, 543, 951, 531)],
+/// );
+/// ```
 pub struct UniformGrid {
     config: Config,
+    stats: Stats,
+
+    /// Preferred aspect ratios of views, by index.
+    view_aspects: HashMap<u32, f32>,
+
+    /// Column count locked in by `Config::grow_downward`.
+    locked_columns: Option<i32>,
+
+    /// Index of the view filling the whole layout area, set by `maximize`.
+    maximized: Option<u32>,
+
+    /// The most recently generated layout.
+    last_layout: Option<GeneratedLayout>,
+
+    /// Insets from the edges of the usable area, set by `set-safe-area`.
+    safe_area: Insets,
+
+    /// Pairs of views whose positions have been exchanged by `swap`, in the
+    /// order they were swapped.
+    swaps: Vec<(usize, usize)>,
+
+    /// Whether to reveal the grid structure, toggled by `show-grid-lines`.
+    show_grid_lines: bool,
+
+    /// Inputs of `last_layout`, to reuse it while river keeps asking for the
+    /// same one. Cleared by every user command, since any of them may change
+    /// the result.
+    cache: Option<LayoutInputs>,
+
+    /// Scales of outputs, by name, as set by `set-output-scale`.
+    output_scales: HashMap<String, f32>,
+
+    /// Tags of views, by index, as set by `set-view-tags`.
+    view_tags: HashMap<u32, u32>,
+
+    /// Size of the grid, and of its cells, from the most recently generated
+    /// layout, if it was a grid.
+    last_grid: Option<(IVec2, IVec2)>,
+
+    /// Transitions between grids, by output and tags, kept after they finish
+    /// to start the next one from.
+    transitions: HashMap<(String, u32), Transition>,
 }
 
 impl UniformGrid {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            stats: Stats::default(),
+            view_aspects: HashMap::new(),
+            locked_columns: None,
+            maximized: None,
+            last_layout: None,
+            safe_area: Insets::default(),
+            swaps: Vec::new(),
+            show_grid_lines: false,
+            cache: None,
+            output_scales: HashMap::new(),
+            view_tags: HashMap::new(),
+            last_grid: None,
+            transitions: HashMap::new(),
+        }
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// A pacer for stepping animations of this layout at the configured rate.
+    pub fn frame_pacer(&self) -> FramePacer {
+        FramePacer::new(self.config.max_fps)
+    }
+
+    /// The most recently generated layout.
+    pub fn last_layout(&self) -> Option<&GeneratedLayout> {
+        self.last_layout.as_ref()
+    }
+
+    /// A hash of the configuration and of everything else that commands
+    /// change, which is the same whenever the same changes have been made.
+    ///
+    /// Being FNV-1a, it is also the same across runs and builds.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = HashedBytes::default();
+        self.config.hash(&mut hasher);
+        sorted_bits(&self.view_aspects).hash(&mut hasher);
+        self.locked_columns.hash(&mut hasher);
+        self.maximized.hash(&mut hasher);
+        self.safe_area.hash(&mut hasher);
+        self.swaps.hash(&mut hasher);
+        self.show_grid_lines.hash(&mut hasher);
+        sorted_bits(&self.output_scales).hash(&mut hasher);
+        let mut view_tags: Vec<_> = self.view_tags.iter().collect();
+        view_tags.sort();
+        view_tags.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether views are still moving between grids, so that generating
+    /// another layout would move them further.
+    pub fn needs_redraw(&self) -> bool {
+        self.transitions
+            .values()
+            .any(|transition| transition.frame < self.config.transition_frames)
+    }
+
+    /// Index of the view containing the given point, according to the most
+    /// recently generated layout.
+    ///
+    /// Returns `None` if the point is in the padding between views, or outside
+    /// of all of them.
+    pub fn view_at(&self, x: i32, y: i32) -> Option<usize> {
+        let (x, y) = (x as i64, y as i64);
+        let views = self
+            .last_layout
+            .as_ref()
+            .map_or(&[][..], |layout| &layout.views);
+        views.iter().position(|view| {
+            (view.x as i64..view.x as i64 + view.width as i64).contains(&x)
+                && (view.y as i64..view.y as i64 + view.height as i64).contains(&y)
+        })
+    }
+
+    /// Replaces the views of `layout` with the next frame of the transition to
+    /// them, for the given output and tags.
+    fn animate(&mut self, mut layout: GeneratedLayout, tags: u32, output: &str) -> GeneratedLayout {
+        let frames = self.config.transition_frames;
+        if frames == 0 {
+            self.transitions.clear();
+            return layout;
+        }
+
+        let grid = self.last_grid.map(|(size, _)| size);
+        let pacer = self.frame_pacer();
+        let transition = self
+            .transitions
+            .entry((output.into(), tags))
+            .or_insert_with(|| Transition {
+                grid,
+                from: Vec::new(),
+                shown: Vec::new(),
+                frame: frames,
+                pacer: pacer.clone(),
+            });
+        if transition.grid != grid {
+            // Start from wherever the views are, even mid-transition.
+            transition.grid = grid;
+            transition.from = std::mem::take(&mut transition.shown);
+            transition.frame = 0;
+            transition.pacer = pacer;
+        }
+        // Until the frame interval has passed, the same frame is shown again.
+        if transition.frame < frames && transition.pacer.step(Instant::now()) {
+            transition.frame += 1;
+        }
+
+        let progress = transition.frame as f32 / frames as f32;
+        for (view, from) in layout.views.iter_mut().zip(&transition.from) {
+            *view = interpolate(from, view, progress);
+        }
+        transition.shown = layout.views.clone();
+        layout
+    }
+
+    fn log_stats(&self) {
+        info!(
+            "layouts generated: {}, commands received: {}, last generation time: {}us",
+            self.stats.layouts_generated,
+            self.stats.commands_received,
+            self.stats.last_generation_time.as_micros(),
+        );
+    }
+
+    /// `riverctl` rules that would place floating views where the most
+    /// recently generated layout placed each view, for the `export-rules`
+    /// command.
+    ///
+    /// river only applies positions and dimensions to floating views, and can
+    /// only match rules by app-id and title rather than by position in the
+    /// stack, so each view's rules float it and match a placeholder app-id to
+    /// be filled in.
+    /// Positions are relative to the usable area, as in the layout.
+    fn export_rules(&self) -> Vec<String> {
+        let Some(layout) = &self.last_layout else {
+            return Vec::new();
+        };
+
+        let mut rules = Vec::new();
+        for (index, view) in layout.views.iter().enumerate() {
+            let matcher = format!("-app-id 'view-{index}'");
+            rules.push(format!("riverctl rule-add {matcher} float"));
+            rules.push(format!(
+                "riverctl rule-add {matcher} position {} {}",
+                view.x, view.y,
+            ));
+            rules.push(format!(
+                "riverctl rule-add {matcher} dimensions {} {}",
+                view.width, view.height,
+            ));
+        }
+        rules
+    }
+
+    /// Commands that would bring a layout with the default configuration to
+    /// the current one, for the `dump-cmd` command.
+    ///
+    /// Only settings that can be changed by a command are included.
+    fn dump_commands(&self) -> Vec<String> {
+        let config = &self.config;
+        let default = Config::default();
+        let mut commands = Vec::new();
+
+        if config.grid_anchor != default.grid_anchor {
+            commands.push(format!(
+                "set-grid-anchor {}% {}%",
+                config.grid_anchor.x * 100.0,
+                config.grid_anchor.y * 100.0,
+            ));
+        }
+        if let Some(region) = &config.region {
+            commands.push(format!(
+                "set-region {}% {}% {}% {}%",
+                region.position.x * 100.0,
+                region.position.y * 100.0,
+                region.size.x * 100.0,
+                region.size.y * 100.0,
+            ));
+        }
+        if let Some(profile) = &config.aspect_profile {
+            commands.push(format!(
+                "set-aspect-profile portrait={} landscape={}",
+                profile.portrait, profile.landscape,
+            ));
+        }
+        if config.target_aspect != default.target_aspect {
+            commands.push(format!("set-aspect {}", config.target_aspect));
+        }
+        if config.aspect_bias != default.aspect_bias {
+            commands.push(format!("set-aspect-bias {}", config.aspect_bias));
+        }
+        if matches!(config.fit, Fit::GridAspect) {
+            commands.push("set-fit grid-aspect".into());
+        }
+        if matches!(config.overflow, Overflow::Sidebar) {
+            commands.push("set-overflow sidebar".into());
+        }
+        if let Some(cell_size) = config.cell_size {
+            commands.push(format!("set-cell-size {}x{}", cell_size.x, cell_size.y));
+        }
+        if config.header_gap != default.header_gap {
+            commands.push(format!("set-header-gap {}", config.header_gap));
+        }
+        if config.decoration_inset != default.decoration_inset {
+            commands.push(format!("set-decoration-inset {}", config.decoration_inset));
+        }
+        if config.transition_frames != default.transition_frames {
+            commands.push(format!(
+                "set-transition-frames {}",
+                config.transition_frames
+            ));
+        }
+        if let Some(wall) = config.wall {
+            commands.push(format!("set-wall {}x{}", wall.x, wall.y));
+        }
+        if let Some(columns) = config.columns {
+            commands.push(format!("set-columns {columns}"));
+        }
+        if config.center_last_row {
+            commands.push("center-last-row".into());
+        }
+        if config.fill_order != default.fill_order {
+            commands.push(format!("set-fill-order {}", config.fill_order));
+        }
+        if config.match_output_aspect {
+            commands.push("match-output-aspect".into());
+        }
+        if let Some(ratio) = config.master_ratio {
+            commands.push(format!("set-master-ratio {ratio}"));
+        }
+        match config.master_location {
+            Edge::Left => {}
+            Edge::Right => commands.push("set-master-location right".into()),
+            Edge::Top => commands.push("set-master-location top".into()),
+            Edge::Bottom => commands.push("set-master-location bottom".into()),
+        }
+        if let Some(seed) = config.tie_break_seed {
+            commands.push(format!("set-tie-break-seed {seed}"));
+        }
+        if let Some(cell) = config.reserve_cell {
+            commands.push(format!("reserve-cell {} {}", cell.x, cell.y));
+        }
+        if config.compact != default.compact {
+            commands.push("compact".into());
+        }
+        if config.tag_filter != default.tag_filter {
+            commands.push("tag-filter".into());
+        }
+        if let Some(fps) = config.max_fps {
+            commands.push(format!("set-max-fps {fps}"));
+        }
+
+        let mut aliases: Vec<_> = config.aliases.iter().collect();
+        aliases.sort();
+        for (name, command) in aliases {
+            commands.push(format!("alias {name} {command}"));
+        }
+
+        commands
+    }
+
+    /// A one-line summary of the most recently generated layout, for the
+    /// `describe` command.
+    fn describe(&self) -> String {
+        let view_count = self
+            .last_layout
+            .as_ref()
+            .map_or(0, |layout| layout.views.len());
+        match self.last_grid {
+            Some((grid, cell_size)) => format!(
+                "{}: {}x{} grid, cells {}x{}, {view_count} views, {} fill",
+                Self::NAMESPACE,
+                grid.y,
+                grid.x,
+                cell_size.x,
+                cell_size.y,
+                self.config.fill_order,
+            ),
+            None => format!("{}: no grid, {view_count} views", Self::NAMESPACE),
+        }
+    }
+
+    /// A copy of the last layout, if it can be reused for the given inputs.
+    fn cached(
+        &self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+        output: &str,
+    ) -> Option<GeneratedLayout> {
+        if self.needs_redraw() {
+            return None;
+        }
+        let cache = self.cache.as_ref()?;
+        let layout = self.last_layout.as_ref()?;
+        let hit = cache.view_count == view_count
+            && cache.usable_width == usable_width
+            && cache.usable_height == usable_height
+            && cache.tags == tags
+            && cache.output == output;
+
+        hit.then(|| GeneratedLayout {
+            layout_name: layout.layout_name.clone(),
+            views: layout.views.clone(),
+        })
     }
 
     fn user_cmd_inner(
@@ -109,12 +1575,753 @@ impl UniformGrid {
         tags: Option<u32>,
         output: &str,
     ) -> Result<(), Error> {
-        let _ = (tags, output);
+        let _ = tags;
+
+        // Any whitespace separates arguments, but other control characters are
+        // most likely a mistake.
+        if cmd.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+            return Err(Error::InvalidArgument("command"));
+        }
+
+        let alias = cmd
+            .split_whitespace()
+            .next()
+            .and_then(|name| self.config.aliases.get(name))
+            .cloned();
+        let cmd = match alias {
+            Some(expansion) => {
+                let args = cmd
+                    .trim_start()
+                    .split_once(char::is_whitespace)
+                    .map_or("", |(_, args)| args);
+                format!("{expansion} {args}")
+            }
+            None => cmd,
+        };
 
         let mut parts = cmd.split_whitespace();
 
         match parts.next().unwrap_or("") {
-            other => Err(Error::UnknownCommand(other.into())),
+            "set-grid-anchor" => {
+                let x = parse_percent(parts.next(), "x")?;
+                let y = parse_percent(parts.next(), "y")?;
+
+                self.config.grid_anchor = Vec2::new(x, y);
+            }
+            "set-view-aspect" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+                let aspect = parts.next().ok_or(Error::MissingArgument("aspect"))?;
+
+                if aspect == "none" {
+                    self.view_aspects.remove(&index);
+                } else {
+                    let aspect = parse_aspect(aspect).ok_or(Error::InvalidArgument("aspect"))?;
+                    self.view_aspects.insert(index, aspect);
+                }
+            }
+            "lock-columns" => {
+                // Anything beyond an `i16` is surely a mistake, and could
+                // overflow the layout arithmetic.
+                let columns: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("columns"))?
+                    .parse()
+                    .ok()
+                    .filter(|&columns| columns > 0)
+                    .ok_or(Error::InvalidArgument("columns"))?;
+
+                self.locked_columns = Some(columns.into());
+            }
+            "maximize" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+
+                self.maximized = Some(index);
+            }
+            "restore" => {
+                if self.maximized.take().is_none() {
+                    return Err(Error::State {
+                        operation: "restore",
+                        state: "no view is maximized".into(),
+                    });
+                }
+            }
+            "set-overflow" => {
+                self.config.overflow =
+                    match parts.next().ok_or(Error::MissingArgument("overflow"))? {
+                        "last-row" => Overflow::LastRow,
+                        "sidebar" => Overflow::Sidebar,
+                        _ => return Err(Error::InvalidArgument("overflow")),
+                    };
+            }
+            "set-fill-order" => {
+                self.config.fill_order =
+                    match parts.next().ok_or(Error::MissingArgument("order"))? {
+                        "snake" => FillOrder::Snake,
+                        "row-major" => FillOrder::RowMajor,
+                        "column-major" => FillOrder::ColumnMajor,
+                        _ => return Err(Error::InvalidArgument("order")),
+                    };
+            }
+            "set-aspect" => {
+                let arg = parts.next().ok_or(Error::MissingArgument("aspect"))?;
+                let aspect = match parts.next() {
+                    Some(height) => parse_aspect(&format!("{arg}:{height}")),
+                    None => parse_aspect(arg),
+                };
+
+                self.config.target_aspect = aspect.ok_or(Error::InvalidArgument("aspect"))?;
+            }
+            "set-aspect-bias" => {
+                let bias: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("bias"))?
+                    .parse()
+                    .ok()
+                    .filter(|bias| (-1.0..=1.0).contains(bias))
+                    .ok_or(Error::InvalidArgument("bias"))?;
+
+                self.config.aspect_bias = bias;
+            }
+            "set-region" => {
+                if parts.clone().next() == Some("none") {
+                    self.config.region = None;
+                    return Ok(());
+                }
+
+                let x = parse_percent(parts.next(), "x")?;
+                let y = parse_percent(parts.next(), "y")?;
+                let width = parse_percent(parts.next(), "width")?;
+                let height = parse_percent(parts.next(), "height")?;
+
+                // Trim the region to the layout area if it extends past it.
+                self.config.region = Some(Region {
+                    position: Vec2::new(x, y),
+                    size: Vec2::new(width.min(1.0 - x), height.min(1.0 - y)),
+                });
+            }
+            "set-fit" => {
+                self.config.fit = match parts.next().ok_or(Error::MissingArgument("fit"))? {
+                    "cell-aspect" => Fit::CellAspect,
+                    "grid-aspect" => Fit::GridAspect,
+                    _ => return Err(Error::InvalidArgument("fit")),
+                };
+            }
+            "set-cell-size" => {
+                let size = parts.next().ok_or(Error::MissingArgument("size"))?;
+
+                self.config.cell_size = if size == "none" {
+                    None
+                } else {
+                    // Anything beyond an `i16` is surely a mistake.
+                    let (width, height) =
+                        size.split_once('x').ok_or(Error::InvalidArgument("size"))?;
+                    let size = width
+                        .parse::<i16>()
+                        .ok()
+                        .zip(height.parse::<i16>().ok())
+                        .map(|(width, height)| IVec2::new(width.into(), height.into()))
+                        .filter(|size| size.cmpgt(IVec2::ZERO).all())
+                        .ok_or(Error::InvalidArgument("size"))?;
+                    Some(size)
+                };
+            }
+            "set-header-gap" => {
+                let gap: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("gap"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("gap"))?;
+
+                self.config.header_gap = gap.into();
+            }
+            "set-transition-frames" => {
+                let frames: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("frames"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("frames"))?;
+
+                self.config.transition_frames = frames;
+            }
+            "set-wall" => {
+                let size = parts.next().ok_or(Error::MissingArgument("size"))?;
+
+                self.config.wall = if size == "none" {
+                    None
+                } else {
+                    // Anything beyond an `i16` is surely a mistake.
+                    let (columns, rows) =
+                        size.split_once('x').ok_or(Error::InvalidArgument("size"))?;
+                    let size = columns
+                        .parse::<i16>()
+                        .ok()
+                        .zip(rows.parse::<i16>().ok())
+                        .map(|(columns, rows)| IVec2::new(columns.into(), rows.into()))
+                        .filter(|size| size.cmpgt(IVec2::ZERO).all())
+                        .ok_or(Error::InvalidArgument("size"))?;
+                    Some(size)
+                };
+            }
+            "center-last-row" => {
+                self.config.center_last_row = !self.config.center_last_row;
+            }
+            "set-columns" => {
+                let columns = parts.next().ok_or(Error::MissingArgument("columns"))?;
+
+                self.config.columns = if columns == "auto" {
+                    None
+                } else {
+                    // Anything beyond an `i16` is surely a mistake.
+                    let columns = columns
+                        .parse::<i16>()
+                        .ok()
+                        .filter(|&columns| columns > 0)
+                        .ok_or(Error::InvalidArgument("columns"))?;
+                    Some(columns as u32)
+                };
+            }
+            "reserve-cell" => {
+                let column = parts.next().ok_or(Error::MissingArgument("column"))?;
+
+                self.config.reserve_cell = if column == "none" {
+                    None
+                } else {
+                    let column: i16 = column
+                        .parse()
+                        .map_err(|_| Error::InvalidArgument("column"))?;
+                    let row: i16 = parts
+                        .next()
+                        .ok_or(Error::MissingArgument("row"))?
+                        .parse()
+                        .map_err(|_| Error::InvalidArgument("row"))?;
+                    Some(IVec2::new(column.into(), row.into()))
+                };
+            }
+            "compact" => {
+                self.config.compact = !self.config.compact;
+            }
+            "set-safe-area" => {
+                // Anything beyond a `u16` is surely a mistake, and could
+                // overflow the layout arithmetic.
+                let mut inset = |name| -> Result<u32, Error> {
+                    parts
+                        .next()
+                        .ok_or(Error::MissingArgument(name))?
+                        .parse::<u16>()
+                        .map(u32::from)
+                        .map_err(|_| Error::InvalidArgument(name))
+                };
+
+                self.safe_area = Insets {
+                    top: inset("top")?,
+                    right: inset("right")?,
+                    bottom: inset("bottom")?,
+                    left: inset("left")?,
+                };
+            }
+            "set-aspect-profile" => {
+                let (mut portrait, mut landscape) = match &self.config.aspect_profile {
+                    Some(profile) => (profile.portrait, profile.landscape),
+                    None => (self.config.target_aspect, self.config.target_aspect),
+                };
+
+                for arg in parts {
+                    if arg == "none" {
+                        self.config.aspect_profile = None;
+                        return Ok(());
+                    }
+                    let (key, value) = arg
+                        .split_once('=')
+                        .ok_or(Error::InvalidArgument("profile"))?;
+                    let aspect = parse_aspect(value).ok_or(Error::InvalidArgument("aspect"))?;
+                    match key {
+                        "portrait" => portrait = aspect,
+                        "landscape" => landscape = aspect,
+                        _ => return Err(Error::InvalidArgument("profile")),
+                    }
+                }
+
+                self.config.aspect_profile = Some(AspectProfile {
+                    portrait,
+                    landscape,
+                });
+            }
+            "set-decoration-inset" => {
+                // Anything beyond an `i16` is surely a mistake, and could
+                // overflow the layout arithmetic.
+                let inset: i16 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("inset"))?
+                    .parse()
+                    .ok()
+                    .filter(|&inset| inset >= 0)
+                    .ok_or(Error::InvalidArgument("inset"))?;
+
+                self.config.decoration_inset = inset.into();
+            }
+            "set-max-fps" => {
+                let arg = parts.next().ok_or(Error::MissingArgument("fps"))?;
+                self.config.max_fps = match arg {
+                    "none" => None,
+                    _ => Some(
+                        arg.parse()
+                            .ok()
+                            .filter(|&fps| fps > 0)
+                            .ok_or(Error::InvalidArgument("fps"))?,
+                    ),
+                };
+            }
+            "swap" => {
+                let mut index = |name| -> Result<usize, Error> {
+                    parts
+                        .next()
+                        .ok_or(Error::MissingArgument(name))?
+                        .parse()
+                        .map_err(|_| Error::InvalidArgument(name))
+                };
+                let swap = (index("i")?, index("j")?);
+
+                self.swaps.push(swap);
+            }
+            "clear-swaps" => {
+                self.swaps.clear();
+            }
+            "show-grid-lines" => {
+                self.show_grid_lines = !self.show_grid_lines;
+            }
+            "reset-view" => {
+                // Only undo temporary changes to the view, keeping the
+                // configuration and what is known about the views.
+                self.maximized = None;
+                self.swaps.clear();
+                self.show_grid_lines = false;
+            }
+            "alias" => {
+                let name = parts.next().ok_or(Error::MissingArgument("name"))?;
+                let command = parts.collect::<Vec<_>>().join(" ");
+                if command.is_empty() {
+                    return Err(Error::MissingArgument("command"));
+                }
+
+                self.config.aliases.insert(name.into(), command);
+            }
+            "set-output-scale" => {
+                let scale: f32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("scale"))?
+                    .parse()
+                    .ok()
+                    .filter(|scale: &f32| scale.is_finite() && *scale > 0.0)
+                    .ok_or(Error::InvalidArgument("scale"))?;
+
+                self.output_scales.insert(output.into(), scale);
+            }
+            "set-view-tags" => {
+                let index: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("index"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("index"))?;
+                let tags: u32 = parts
+                    .next()
+                    .ok_or(Error::MissingArgument("tags"))?
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument("tags"))?;
+
+                self.view_tags.insert(index, tags);
+            }
+            "set-tie-break-seed" => {
+                let seed = parts.next().ok_or(Error::MissingArgument("seed"))?;
+
+                self.config.tie_break_seed = if seed == "none" {
+                    None
+                } else {
+                    Some(seed.parse().map_err(|_| Error::InvalidArgument("seed"))?)
+                };
+            }
+            "set-master-ratio" => {
+                let ratio = parts.next().ok_or(Error::MissingArgument("ratio"))?;
+
+                self.config.master_ratio = if ratio == "none" {
+                    None
+                } else {
+                    let ratio = ratio
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|ratio| 0.0 < *ratio && *ratio < 1.0)
+                        .ok_or(Error::InvalidArgument("ratio"))?;
+                    Some(ratio)
+                };
+            }
+            "set-master-location" => {
+                self.config.master_location =
+                    match parts.next().ok_or(Error::MissingArgument("location"))? {
+                        "left" => Edge::Left,
+                        "right" => Edge::Right,
+                        "top" => Edge::Top,
+                        "bottom" => Edge::Bottom,
+                        _ => return Err(Error::InvalidArgument("location")),
+                    };
+            }
+            "match-output-aspect" => {
+                self.config.match_output_aspect = !self.config.match_output_aspect;
+            }
+            "tag-filter" => {
+                self.config.tag_filter = !self.config.tag_filter;
+            }
+            "describe" => {
+                info!("{}", self.describe());
+            }
+            "dump-cmd" => {
+                for command in self.dump_commands() {
+                    info!("riverctl send-layout-cmd {} '{command}'", Self::NAMESPACE);
+                }
+            }
+            "export-rules" => {
+                for rule in self.export_rules() {
+                    info!("{rule}");
+                }
+            }
+            "stats" => {
+                self.log_stats();
+            }
+            "state-hash" => {
+                info!("state-hash {:016x}", self.state_hash());
+            }
+            other => return Err(Error::UnknownCommand(other.into())),
+        }
+        Ok(())
+    }
+
+    fn generate_layout_inner(
+        &mut self,
+        view_count: u32,
+        usable_width: u32,
+        usable_height: u32,
+        tags: u32,
+        output: &str,
+    ) -> Result<GeneratedLayout, Error> {
+        let _ = (tags, output);
+
+        let output_size = IVec2::new(usable_width as i32, usable_height as i32);
+
+        if let Some(maximized) = self.maximized.filter(|&index| index < view_count) {
+            let padded_size = output_size - 2 * IVec2::splat(self.config.outer_padding);
+            let views = (0..view_count).map(|i_view| {
+                let size = if i_view == maximized {
+                    padded_size.max(IVec2::ZERO)
+                } else {
+                    IVec2::ZERO
+                };
+                Rectangle {
+                    x: self.config.outer_padding,
+                    y: self.config.outer_padding,
+                    width: size.x as u32,
+                    height: size.y as u32,
+                }
+            });
+
+            self.last_grid = None;
+            return Ok(GeneratedLayout {
+                layout_name: format!("{}: maximized", Self::NAMESPACE),
+                views: views.collect(),
+            });
+        }
+
+        let mut layout = match self.config.master_ratio.filter(|_| view_count > 1) {
+            Some(ratio) => self.generate_master_layout(ratio, view_count, output_size),
+            None => self.generate_grid(view_count, output_size),
+        };
+        self.finish_views(&mut layout.views);
+
+        Ok(layout)
+    }
+
+    /// Lays out the views in a grid filling the given area, before
+    /// `finish_views`.
+    fn generate_grid(&mut self, view_count: u32, output_size: IVec2) -> GeneratedLayout {
+        // The reserved cell takes up room in the grid like another view.
+        let cell_count = view_count + self.config.reserve_cell.is_some() as u32;
+
+        let mut grid = match self.config.wall {
+            Some(size) => Grid { size },
+            None => Grid::choose(&self.config, cell_count, output_size),
+        };
+        if self.config.grow_downward && self.config.wall.is_none() {
+            if view_count == 0 {
+                self.locked_columns = None;
+            } else {
+                let columns = *self.locked_columns.get_or_insert(grid.size.x);
+                grid = Grid::with_columns(columns, cell_count);
+            }
+        }
+
+        if self.config.compact && view_count > 0 {
+            info!(
+                "{view_count} views in {}x{} grid, {:.1}% of cells unused",
+                grid.size.y,
+                grid.size.x,
+                100.0 * grid.unused_fraction(cell_count),
+            );
+        }
+
+        // Only full rows remain in the grid when the rest of the views are
+        // moved to the sidebar.
+        let columns = grid.size.x;
+        let sidebar_count = match self.config.overflow {
+            Overflow::Sidebar if cell_count as i32 > columns && self.config.wall.is_none() => {
+                (cell_count as i32 % columns).min(view_count as i32)
+            }
+            _ => 0,
+        };
+        if sidebar_count > 0 {
+            grid.size = IVec2::new(columns + 1, cell_count as i32 / columns);
+        }
+        let reserved = self.config.reserve_cell.map(|cell| {
+            IVec2::select(
+                cell.cmplt(IVec2::ZERO),
+                grid.size.with_x(columns) + cell,
+                cell,
+            )
+        });
+
+        // Generate cell views in the fill order
+        let layout = grid.layout(&self.config, output_size);
+        self.last_grid = Some((grid.size, layout.view_size));
+        let cells: Vec<IVec2> = (0..columns * grid.size.y)
+            .map(|i_view| {
+                let column_base = i_view % columns;
+                let row = i_view / columns;
+                let (column, row) = match self.config.fill_order {
+                    FillOrder::Snake if row % 2 == 1 => (columns - 1 - column_base, row),
+                    FillOrder::Snake | FillOrder::RowMajor => (column_base, row),
+                    FillOrder::ColumnMajor => (i_view / grid.size.y, i_view % grid.size.y),
+                };
+                let row = match self.config.row_order {
+                    RowOrder::TopDown => row,
+                    RowOrder::BottomUp => grid.size.y - 1 - row,
+                };
+                IVec2::new(column, row)
+            })
+            .filter(|&cell| Some(cell) != reserved)
+            .take((view_count as i32 - sidebar_count) as usize)
+            .collect();
+
+        // Only a wall can run out of cells.
+        let hidden_count = view_count as usize - sidebar_count as usize - cells.len();
+        if hidden_count > 0 {
+            warn!(
+                "{view_count} views don't fit in the {}x{} wall, hiding {hidden_count} of them",
+                grid.size.y, grid.size.x,
+            );
+        }
+        let hidden_views = std::iter::repeat_n(
+            Rectangle {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            },
+            hidden_count,
+        );
+
+        // Views of a partial last row move by the number of cells that
+        // centers them, from whichever end of the row they start at.
+        let last_row_shift = cells
+            .last()
+            .filter(|_| {
+                self.config.center_last_row
+                    && !matches!(self.config.fill_order, FillOrder::ColumnMajor)
+            })
+            .filter(|last| reserved.is_none_or(|cell| cell.y != last.y))
+            .and_then(|last| {
+                let row: Vec<i32> = cells
+                    .iter()
+                    .filter(|cell| cell.y == last.y)
+                    .map(|cell| cell.x)
+                    .collect();
+                let first_column = *row.iter().min()?;
+                let empty_count = columns - row.len() as i32;
+                let shift = empty_count as f32 / 2.0 - first_column as f32;
+                Some((last.y, (shift * layout.stride.x).round() as i32))
+            });
+
+        // Groups are looked up by cell, within the bounding box of the cells,
+        // and only when there are groups to join at all.
+        let groups = group_ids(&self.config.groups, cells.len());
+        let min_cell = cells.iter().copied().reduce(IVec2::min).unwrap_or_default();
+        let box_size = cells
+            .iter()
+            .copied()
+            .reduce(IVec2::max)
+            .map_or(IVec2::ZERO, |max_cell| max_cell - min_cell + IVec2::ONE);
+        let mut cell_groups = Vec::new();
+        if !self.config.groups.is_empty() {
+            cell_groups = vec![None; box_size.x as usize * box_size.y as usize];
+            for (&cell, &group) in cells.iter().zip(&groups) {
+                let cell = cell - min_cell;
+                cell_groups[cell.y as usize * box_size.x as usize + cell.x as usize] = Some(group);
+            }
+        }
+        let group_at = |cell: IVec2| {
+            let cell = cell - min_cell;
+            if cell.cmplt(IVec2::ZERO).any() || cell.cmpge(box_size).any() {
+                return None;
+            }
+            cell_groups
+                .get(cell.y as usize * box_size.x as usize + cell.x as usize)
+                .copied()
+                .flatten()
+        };
+        let cell_views = cells.iter().zip(&groups).map(|(&cell, &group)| {
+            let joined = |direction: IVec2| group_at(cell + direction) == Some(group);
+            let mut view = layout.at(
+                cell,
+                Joined {
+                    left: joined(-IVec2::X),
+                    right: joined(IVec2::X),
+                    top: joined(-IVec2::Y),
+                    bottom: joined(IVec2::Y),
+                },
+            );
+            if let Some((_, shift)) = last_row_shift.filter(|&(row, _)| row == cell.y) {
+                view.x = view.x.saturating_add(shift);
+            }
+            view
+        });
+
+        let mut sidebar_views = Vec::new();
+        if sidebar_count > 0 {
+            let top = layout.at(IVec2::new(columns, 0), Joined::default());
+            let bottom = layout.at(IVec2::new(columns, grid.size.y - 1), Joined::default());
+            let height = bottom.y.saturating_add(bottom.height as i32) - top.y;
+            let stride = (height + self.config.view_padding) as f32 / sidebar_count as f32;
+            sidebar_views.extend((0..sidebar_count).map(|i_view| Rectangle {
+                x: top.x,
+                y: top.y.saturating_add((stride * i_view as f32) as i32),
+                width: top.width,
+                height: (stride as i32 - self.config.view_padding).max(0) as u32,
+            }));
+        }
+
+        let views = cell_views
+            .chain(hidden_views)
+            .chain(sidebar_views)
+            .collect();
+
+        let mut layout_name = format!("{}: {}x{}", Self::NAMESPACE, grid.size.y, grid.size.x);
+        if self.show_grid_lines {
+            layout_name += &format!(" grid, {}x{} cells", layout.view_size.x, layout.view_size.y);
+        }
+
+        GeneratedLayout { layout_name, views }
+    }
+
+    /// Applies what every view of a layout gets, however it was laid out:
+    /// decoration insets, snapping, swaps, and grid lines.
+    fn finish_views(&self, views: &mut [Rectangle]) {
+        for view in views.iter_mut() {
+            let inset = self.config.decoration_inset.clamp(0, view.height as i32);
+            view.y = view.y.saturating_add(inset);
+            view.height -= inset as u32;
+
+            if let Some(step) = self.config.global_grid_step.filter(|&step| step > 0) {
+                *view = snap(view, step);
+            }
+        }
+
+        self.apply_swaps(views);
+
+        if self.show_grid_lines {
+            // Widen the gaps between views, so that the cell boundaries
+            // stand out on screen as well as in the layout name.
+            for view in views {
+                *view = inset(view, GRID_LINE_WIDTH);
+            }
+        }
+    }
+
+    /// Lays out the first view in the master area of `Config::master_ratio`,
+    /// and the rest of them in a grid in the remaining area.
+    fn generate_master_layout(
+        &mut self,
+        ratio: f32,
+        view_count: u32,
+        output_size: IVec2,
+    ) -> GeneratedLayout {
+        let (master_size, stack_size) = match self.config.master_location {
+            Edge::Left | Edge::Right => {
+                let width = (output_size.x as f32 * ratio) as i32;
+                (
+                    output_size.with_x(width),
+                    output_size.with_x(output_size.x - width),
+                )
+            }
+            Edge::Top | Edge::Bottom => {
+                let height = (output_size.y as f32 * ratio) as i32;
+                (
+                    output_size.with_y(height),
+                    output_size.with_y(output_size.y - height),
+                )
+            }
+        };
+        let (master_position, stack_offset) = match self.config.master_location {
+            Edge::Left => (IVec2::ZERO, IVec2::new(master_size.x, 0)),
+            Edge::Right => (IVec2::new(stack_size.x, 0), IVec2::ZERO),
+            Edge::Top => (IVec2::ZERO, IVec2::new(0, master_size.y)),
+            Edge::Bottom => (IVec2::new(0, stack_size.y), IVec2::ZERO),
+        };
+
+        // The outer padding of the stack's grid already separates it from the
+        // master view, which is only padded along the other edges.
+        let padding = self.config.outer_padding;
+        let mut min = master_position + padding;
+        let mut max = master_position + master_size - padding;
+        match self.config.master_location {
+            Edge::Left => max.x += padding,
+            Edge::Right => min.x -= padding,
+            Edge::Top => max.y += padding,
+            Edge::Bottom => min.y -= padding,
+        }
+        let size = (max - min).max(IVec2::ZERO);
+        let master_view = Rectangle {
+            x: min.x,
+            y: min.y,
+            width: size.x as u32,
+            height: size.y as u32,
+        };
+
+        // Lay out the stack as a grid of its own, finished along with the
+        // master view.
+        let stack = self.generate_grid(view_count - 1, stack_size.max(IVec2::ZERO));
+
+        let mut views = vec![master_view];
+        views.extend(stack.views.into_iter().map(|view| Rectangle {
+            x: view.x.saturating_add(stack_offset.x),
+            y: view.y.saturating_add(stack_offset.y),
+            ..view
+        }));
+
+        GeneratedLayout {
+            layout_name: format!("{} + master", stack.layout_name),
+            views,
+        }
+    }
+
+    /// Exchanges the positions of the views swapped by `swap`.
+    fn apply_swaps(&self, views: &mut [Rectangle]) {
+        for &(i, j) in &self.swaps {
+            if i < views.len() && j < views.len() {
+                views.swap(i, j);
+            }
         }
     }
 }
@@ -130,6 +2337,8 @@ impl Layout for UniformGrid {
         tags: Option<u32>,
         output: &str,
     ) -> Result<(), Self::Error> {
+        self.stats.commands_received += 1;
+        self.cache = None;
         let result = self.user_cmd_inner(cmd, tags, output);
         if let Err(e) = &result {
             error!("{e}");
@@ -146,48 +2355,108 @@ impl Layout for UniformGrid {
         tags: u32,
         output: &str,
     ) -> Result<GeneratedLayout, Self::Error> {
-        let _ = (tags, output);
-
-        let output_size = IVec2::new(usable_width as i32, usable_height as i32);
-
-        let mut grid = Grid { size: IVec2::ONE };
-
-        while (grid.total_cells() as u32) < view_count {
-            let options = [
-                Grid {
-                    size: grid.size + IVec2::X,
-                },
-                Grid {
-                    size: grid.size + IVec2::Y,
-                },
-            ];
-            grid = options
-                .into_iter()
-                .min_by_key(|grid| {
-                    let eff = grid
-                        .layout(&self.config, output_size)
-                        .efficiency(self.config.target_aspect);
-                    (eff * 1000000.0) as i32
-                })
-                .unwrap();
+        if view_count > MAX_VIEW_COUNT {
+            return Err(Error::TooManyViews(view_count));
         }
-
-        // Generate cell views in a snaking layout
-        let layout = grid.layout(&self.config, output_size);
-        let views = (0..view_count as i32).map(|i_view| {
-            let column_base = i_view % grid.size.x;
-            let row = i_view / grid.size.x;
-            let column = if row % 2 == 0 {
-                column_base
-            } else {
-                grid.size.x - 1 - column_base
+        let start = Instant::now();
+        let safe_width = usable_width
+            .saturating_sub(self.safe_area.left.saturating_add(self.safe_area.right))
+            .min(MAX_USABLE_SIZE);
+        let safe_height = usable_height
+            .saturating_sub(self.safe_area.top.saturating_add(self.safe_area.bottom))
+            .min(MAX_USABLE_SIZE);
+        let (region_x, region_y, region_width, region_height) = match &self.config.region {
+            Some(region) => (
+                (safe_width as f32 * region.position.x) as u32,
+                (safe_height as f32 * region.position.y) as u32,
+                (safe_width as f32 * region.size.x) as u32,
+                (safe_height as f32 * region.size.y) as u32,
+            ),
+            None => (0, 0, safe_width, safe_height),
+        };
+        let offset_x = self.safe_area.left.saturating_add(region_x);
+        let offset_y = self.safe_area.top.saturating_add(region_y);
+        // Paddings are only scaled for the duration of this layout.
+        let unscaled_padding = (self.config.outer_padding, self.config.view_padding);
+        if self.config.logical_padding {
+            let scale = self.output_scales.get(output).copied().unwrap_or(1.0);
+            // Scaled paddings are bounded like the ones set by commands.
+            let scaled = |padding: i32| {
+                ((padding as f32 * scale).round() as i32).clamp(i16::MIN.into(), i16::MAX.into())
             };
-            layout.at(IVec2::new(column, row))
-        });
+            self.config.outer_padding = scaled(self.config.outer_padding);
+            self.config.view_padding = scaled(self.config.view_padding);
+        }
+        let shown: Vec<u32> = (0..view_count)
+            .filter(|index| {
+                !self.config.tag_filter
+                    || self
+                        .view_tags
+                        .get(index)
+                        .is_none_or(|view_tags| view_tags & tags != 0)
+            })
+            .collect();
+        let result = match self.cached(view_count, usable_width, usable_height, tags, output) {
+            Some(layout) => Ok(layout),
+            None => self
+                .generate_layout_inner(
+                    shown.len() as u32,
+                    region_width,
+                    region_height,
+                    tags,
+                    output,
+                )
+                .map(|mut layout| {
+                    // Put the shown views back in their places in the stack,
+                    // collapsing the hidden ones.
+                    if shown.len() < view_count as usize {
+                        let hidden = Rectangle {
+                            x: 0,
+                            y: 0,
+                            width: 0,
+                            height: 0,
+                        };
+                        let mut views = vec![hidden; view_count as usize];
+                        for (&index, view) in shown.iter().zip(layout.views) {
+                            views[index as usize] = view;
+                        }
+                        layout.views = views;
+                    }
+                    for view in &mut layout.views {
+                        view.x = view.x.saturating_add(offset_x as i32);
+                        view.y = view.y.saturating_add(offset_y as i32);
+                    }
+                    if self.config.respect_client_aspect {
+                        for (&index, &aspect) in &self.view_aspects {
+                            if let Some(view) = layout.views.get_mut(index as usize) {
+                                *view = letterbox(view, aspect);
+                            }
+                        }
+                    }
+                    layout
+                }),
+        };
+        let result = result.map(|layout| self.animate(layout, tags, output));
+        (self.config.outer_padding, self.config.view_padding) = unscaled_padding;
+        if let Ok(layout) = &result {
+            self.last_layout = Some(GeneratedLayout {
+                layout_name: layout.layout_name.clone(),
+                views: layout.views.clone(),
+            });
+            self.cache = Some(LayoutInputs {
+                view_count,
+                usable_width,
+                usable_height,
+                tags,
+                output: output.into(),
+            });
+        }
+        self.stats.layouts_generated += 1;
+        self.stats.last_generation_time = start.elapsed();
 
-        Ok(GeneratedLayout {
-            layout_name: format!("{}: {}x{}", Self::NAMESPACE, grid.size.y, grid.size.x),
-            views: views.collect(),
-        })
+        result
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -1,8 +1,29 @@
+#[path = "../../shared/once.rs"]
+mod once;
+
+use clap::Parser;
 use river_uniform_grid_layout::UniformGrid;
 
+#[derive(Parser)]
+struct Args {
+    /// Generate a single layout and print it as JSON, instead of running as a
+    /// layout generator.
+    ///
+    /// The layout parameters are read from standard input, as
+    /// `<view_count> <width> <height> <tags> <output>`.
+    #[arg(long)]
+    once: bool,
+}
+
 fn main() -> anyhow::Result<()> {
     env_logger::init();
+    let args = Args::parse();
+
+    let layout = UniformGrid::new(Default::default());
+    if args.once {
+        return once::once(layout);
+    }
 
-    river_layout_toolkit::run(UniformGrid::new(Default::default()))?;
+    river_layout_toolkit::run(layout)?;
     Ok(())
 }
@@ -0,0 +1,1565 @@
+use river_layout_toolkit::{GeneratedLayout, Layout, Rectangle};
+
+use super::*;
+
+fn layout(grid: &mut UniformGrid, view_count: u32, width: u32, height: u32) -> GeneratedLayout {
+    grid.generate_layout(view_count, width, height, 1, "test")
+        .unwrap()
+}
+
+fn cmd(grid: &mut UniformGrid, cmd: &str) {
+    grid.user_cmd(cmd.into(), None, "test").unwrap();
+}
+
+/// Views as `(x, y, width, height)`, to compare them whole.
+fn rects(views: &[Rectangle]) -> Vec<(i32, i32, u32, u32)> {
+    views
+        .iter()
+        .map(|view| (view.x, view.y, view.width, view.height))
+        .collect()
+}
+
+/// Output sizes to check layouts across: landscape, portrait, and odd.
+const OUTPUT_SIZES: [(u32, u32); 4] = [(1920, 1080), (1080, 1920), (1366, 768), (801, 601)];
+
+/// Asserts river's coordinate conventions: every view lies within the usable
+/// area, whose origin is its top-left corner.
+fn assert_within(views: &[Rectangle], width: u32, height: u32) {
+    for view in views {
+        assert!(view.x >= 0 && view.y >= 0, "{view:?}");
+        assert!(
+            view.x as u32 + view.width <= width && view.y as u32 + view.height <= height,
+            "{view:?} past {width}x{height}",
+        );
+    }
+}
+
+#[test]
+fn prefer_exact_fills_every_cell_when_it_can() {
+    let exact = || {
+        UniformGrid::new(Config {
+            prefer_exact: true,
+            ..Config::default()
+        })
+    };
+    let generated = layout(&mut exact(), 6, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 2x3");
+    assert_eq!(
+        rects(&generated.views),
+        [
+            (6, 6, 632, 531),
+            (644, 6, 632, 531),
+            (1282, 6, 632, 531),
+            (1282, 543, 632, 531),
+            (644, 543, 632, 531),
+            (6, 543, 632, 531),
+        ],
+    );
+
+    // On a portrait output, the best fit for the aspect ratio alone leaves two
+    // of eight cells empty.
+    let mut grid = UniformGrid::new(Config::default());
+    assert_eq!(
+        layout(&mut grid, 6, 1080, 1920).layout_name,
+        "uniform-grid: 4x2"
+    );
+    for (width, height) in OUTPUT_SIZES {
+        let name = layout(&mut exact(), 6, width, height).layout_name;
+        let (rows, columns) = name
+            .strip_prefix("uniform-grid: ")
+            .and_then(|size| size.split_once('x'))
+            .unwrap();
+        assert_eq!(
+            rows.parse::<u32>().unwrap() * columns.parse::<u32>().unwrap(),
+            6,
+            "{name} on {width}x{height}",
+        );
+    }
+}
+
+#[test]
+fn rivertile_preset_leaves_rivertile_gaps() {
+    // rivertile's 6 pixels of outer padding and 6 pixels of view padding on
+    // every side of every view leave 12 pixels at the edges and between views.
+    let mut grid = UniformGrid::new(Config::rivertile());
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (12, 12, 942, 522),
+            (966, 12, 942, 522),
+            (966, 546, 942, 522),
+            (12, 546, 942, 522),
+        ],
+    );
+}
+
+#[test]
+fn groups_are_packed_without_padding_between_their_views() {
+    let mut grid = UniformGrid::new(Config {
+        groups: vec![2, 2],
+        ..Config::default()
+    });
+    let views = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    assert_eq!(
+        views,
+        [
+            (6, 6, 954, 531),
+            (960, 6, 954, 531),
+            (960, 543, 954, 531),
+            (6, 543, 954, 531),
+        ],
+    );
+
+    // No gap within either group, and `view_padding` between them.
+    assert_eq!(views[0].0 + views[0].2 as i32, views[1].0);
+    assert_eq!(views[3].0 + views[3].2 as i32, views[2].0);
+    assert_eq!(views[0].1 + views[0].3 as i32 + 6, views[3].1);
+}
+
+#[test]
+fn groups_join_their_cells_wherever_the_grid_puts_them() {
+    let mut grid = UniformGrid::new(Config {
+        groups: vec![3],
+        row_order: RowOrder::BottomUp,
+        ..Config::default()
+    });
+    let views = layout(&mut grid, 4, 1920, 1080).views;
+
+    // The group snakes along the bottom row and up into the top one.
+    assert_eq!(views[0].x + views[0].width as i32, views[1].x);
+    assert_eq!(views[2].y + views[2].height as i32, views[1].y);
+    assert_eq!(views[3].x + views[3].width as i32 + 6, views[2].x);
+    assert_eq!(views[3].y + views[3].height as i32 + 6, views[0].y);
+}
+
+#[test]
+fn stats_count_layouts_and_commands() {
+    let mut grid = UniformGrid::new(Config::default());
+    for view_count in [1, 2, 3, 3, 3] {
+        layout(&mut grid, view_count, 1920, 1080);
+    }
+    cmd(&mut grid, "stats");
+    assert!(grid
+        .user_cmd("no-such-command".into(), None, "test")
+        .is_err());
+
+    assert_eq!(grid.stats().layouts_generated, 5);
+    assert_eq!(grid.stats().commands_received, 2);
+}
+
+#[test]
+fn grid_anchor_places_the_grid_within_the_leftover_space() {
+    // Cells of 391x291 pixels leave a pixel of the 789x589 within the outer
+    // padding, which the anchor puts before or after the grid.
+    let anchors = [
+        ("0% 0%", [(6, 6), (403, 6), (403, 303), (6, 303)]),
+        ("100% 100%", [(6, 6), (404, 6), (404, 304), (6, 304)]),
+    ];
+    for (anchor, origins) in anchors {
+        let mut grid = UniformGrid::new(Config::default());
+        cmd(&mut grid, &format!("set-grid-anchor {anchor}"));
+        let views = rects(&layout(&mut grid, 4, 801, 601).views);
+        let expected: Vec<_> = origins.map(|(x, y)| (x, y, 391, 291)).into();
+        assert_eq!(views, expected, "{anchor}");
+    }
+
+    // The far edges of the grid end at the outer padding.
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-grid-anchor 100% 100%");
+    let views = layout(&mut grid, 4, 801, 601).views;
+    assert_eq!(views[2].x as u32 + views[2].width, 801 - 6);
+    assert_eq!(views[2].y as u32 + views[2].height, 601 - 6);
+}
+
+#[test]
+fn grid_anchor_places_a_smaller_grid_within_the_leftover_space() {
+    // Three rows of four 400x300 cells, with 6 pixels between them, take up
+    // 1618x912 of the 1908x1068 pixels within the outer padding, leaving
+    // 290x156.
+    let anchors = [
+        ("0% 0%", (6, 6)),
+        ("50% 50%", (151, 84)),
+        ("100% 100%", (296, 162)),
+        ("25% 75%", (78, 123)),
+    ];
+    for (anchor, (x, y)) in anchors {
+        let mut grid = UniformGrid::new(Config::default());
+        cmd(&mut grid, "set-cell-size 400x300");
+        cmd(&mut grid, &format!("set-grid-anchor {anchor}"));
+        let views = rects(&layout(&mut grid, 4, 1920, 1080).views);
+        let expected: Vec<_> = (0..4).map(|i| (x + 406 * i, y, 400, 300)).collect();
+        assert_eq!(views, expected, "{anchor}");
+    }
+}
+
+#[test]
+fn hinted_views_are_letterboxed_to_their_aspect() {
+    let mut grid = UniformGrid::new(Config {
+        respect_client_aspect: true,
+        ..Config::default()
+    });
+    cmd(&mut grid, "set-view-aspect 0 1:1");
+    assert_eq!(
+        rects(&layout(&mut grid, 3, 1920, 1080).views),
+        [(216, 6, 531, 531), (963, 6, 951, 531), (963, 543, 951, 531),],
+    );
+
+    // Hints are ignored unless they are respected.
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-view-aspect 0 1:1");
+    assert_eq!(
+        rects(&layout(&mut grid, 3, 1920, 1080).views)[0],
+        (6, 6, 951, 531),
+    );
+}
+
+#[test]
+fn growing_downward_keeps_views_in_their_columns() {
+    // The first layout locks in a single column.
+    let mut grid = UniformGrid::new(Config {
+        grow_downward: true,
+        ..Config::default()
+    });
+    for view_count in 1..=9 {
+        let generated = layout(&mut grid, view_count, 1920, 1080);
+        assert_eq!(
+            generated.layout_name,
+            format!("uniform-grid: {view_count}x1")
+        );
+        assert!(generated
+            .views
+            .iter()
+            .all(|view| view.x == 6 && view.width == 1908));
+    }
+
+    // With three columns, each view stays in the column it first appeared in
+    // as more are opened.
+    let mut grid = UniformGrid::new(Config {
+        grow_downward: true,
+        ..Config::default()
+    });
+    cmd(&mut grid, "lock-columns 3");
+    let mut columns: Vec<(i32, u32)> = Vec::new();
+    for view_count in 1..=9 {
+        let views = layout(&mut grid, view_count, 1920, 1080).views;
+        for (column, view) in columns.iter().zip(&views) {
+            assert_eq!(*column, (view.x, view.width), "{view_count} views");
+        }
+        let newest = views.last().unwrap();
+        columns.push((newest.x, newest.width));
+    }
+    assert_eq!(
+        columns,
+        [6, 644, 1282, 1282, 644, 6, 6, 644, 1282].map(|x| (x, 632)),
+    );
+}
+
+#[test]
+fn negative_scroll_offsets_leave_space_above_the_first_row() {
+    let mut grid = UniformGrid::new(Config {
+        scroll_offset: -0.5,
+        ..Config::default()
+    });
+    // Half of the 537-pixel row stride is left empty above the first row.
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (6, 274, 951, 531),
+            (963, 274, 951, 531),
+            (963, 811, 951, 531),
+            (6, 811, 951, 531),
+        ],
+    );
+}
+
+#[test]
+fn maximize_fills_the_layout_area_with_one_view() {
+    let mut grid = UniformGrid::new(Config::default());
+    let grid_views = rects(&layout(&mut grid, 4, 1920, 1080).views);
+
+    cmd(&mut grid, "maximize 2");
+    let maximized = layout(&mut grid, 4, 1920, 1080);
+    assert_eq!(maximized.layout_name, "uniform-grid: maximized");
+    assert_eq!(
+        rects(&maximized.views),
+        [(6, 6, 0, 0), (6, 6, 0, 0), (6, 6, 1908, 1068), (6, 6, 0, 0),],
+    );
+
+    cmd(&mut grid, "restore");
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), grid_views);
+    assert!(matches!(
+        grid.user_cmd("restore".into(), None, "test"),
+        Err(Error::State {
+            operation: "restore",
+            ..
+        }),
+    ));
+}
+
+#[test]
+fn coordinates_increase_rightward_and_downward() {
+    for (width, height) in OUTPUT_SIZES {
+        let mut grid = UniformGrid::new(Config::default());
+        for view_count in 1..=9 {
+            let views = layout(&mut grid, view_count, width, height).views;
+            assert_within(&views, width, height);
+
+            // The first row runs rightward from the top-left corner.
+            if let [first, second, ..] = &views[..] {
+                assert!(second.y > first.y || second.x > first.x);
+            }
+            // Snaking through the grid, each view is beside the previous one
+            // in its row, or starts a new row below it.
+            for pair in views.windows(2) {
+                let (previous, next) = (&pair[0], &pair[1]);
+                assert!(
+                    (next.y == previous.y && next.x != previous.x)
+                        || (next.y > previous.y && next.x == previous.x),
+                    "{previous:?} then {next:?}",
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn compact_grids_have_the_fewest_cells_that_fit() {
+    let mut grid = UniformGrid::new(Config {
+        compact: true,
+        ..Config::default()
+    });
+    let generated = layout(&mut grid, 5, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 2x3");
+    assert_eq!(
+        rects(&generated.views),
+        [
+            (6, 6, 632, 531),
+            (644, 6, 632, 531),
+            (1282, 6, 632, 531),
+            (1282, 543, 632, 531),
+            (644, 543, 632, 531),
+        ],
+    );
+
+    // One of the six cells is left empty.
+    let chosen = Grid {
+        size: IVec2::new(3, 2),
+    };
+    assert_eq!(chosen.unused_fraction(5), 1.0 / 6.0);
+
+    // Seven views would otherwise be laid out in a 3x3 grid.
+    assert_eq!(
+        layout(&mut grid, 7, 1920, 1080).layout_name,
+        "uniform-grid: 2x4"
+    );
+    let mut loose = UniformGrid::new(Config::default());
+    assert_eq!(
+        layout(&mut loose, 7, 1920, 1080).layout_name,
+        "uniform-grid: 3x3"
+    );
+}
+
+#[test]
+fn view_at_finds_the_view_under_a_point() {
+    let mut grid = UniformGrid::new(Config::default());
+    assert_eq!(grid.view_at(481, 271), None);
+
+    layout(&mut grid, 4, 1920, 1080);
+    // The centers of the views, which snake back along the second row.
+    assert_eq!(grid.view_at(481, 271), Some(0));
+    assert_eq!(grid.view_at(1438, 271), Some(1));
+    assert_eq!(grid.view_at(1438, 808), Some(2));
+    assert_eq!(grid.view_at(481, 808), Some(3));
+    // The padding between and around them.
+    assert_eq!(grid.view_at(960, 271), None);
+    assert_eq!(grid.view_at(481, 540), None);
+    assert_eq!(grid.view_at(1917, 1077), None);
+}
+
+#[test]
+fn bottom_up_row_order_starts_in_the_bottom_row() {
+    let mut grid = UniformGrid::new(Config {
+        row_order: RowOrder::BottomUp,
+        ..Config::default()
+    });
+    // The views still snake, back along the row above.
+    assert_eq!(
+        rects(&layout(&mut grid, 5, 1920, 1080).views),
+        [
+            (6, 543, 632, 531),
+            (644, 543, 632, 531),
+            (1282, 543, 632, 531),
+            (1282, 6, 632, 531),
+            (644, 6, 632, 531),
+        ],
+    );
+}
+
+#[test]
+fn safe_area_insets_the_whole_layout() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-safe-area 40 10 20 30");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (36, 46, 931, 501),
+            (973, 46, 931, 501),
+            (973, 553, 931, 501),
+            (36, 553, 931, 501),
+        ],
+    );
+
+    for (width, height) in OUTPUT_SIZES {
+        for view_count in 1..10 {
+            let mut views = layout(&mut grid, view_count, width, height).views;
+            for view in &mut views {
+                view.x -= 30;
+                view.y -= 40;
+            }
+            assert_within(&views, width - 40, height - 60);
+        }
+    }
+
+    assert!(matches!(
+        grid.user_cmd("set-safe-area 1 2 3 -4".into(), None, "test"),
+        Err(Error::InvalidArgument("left")),
+    ));
+}
+
+#[test]
+fn global_grid_step_snaps_view_edges() {
+    let mut grid = UniformGrid::new(Config {
+        global_grid_step: Some(100),
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut grid, 6, 1920, 1080).views),
+        [
+            (0, 0, 600, 500),
+            (600, 0, 700, 500),
+            (1300, 0, 600, 500),
+            (1300, 500, 600, 600),
+            (600, 500, 700, 600),
+            (0, 500, 600, 600),
+        ],
+    );
+
+    let mut grid = UniformGrid::new(Config {
+        global_grid_step: Some(8),
+        ..Config::default()
+    });
+    for (width, height) in OUTPUT_SIZES {
+        for view_count in 1..10 {
+            for view in layout(&mut grid, view_count, width, height).views {
+                assert!(view.width > 0 && view.height > 0, "{view:?}");
+                for edge in [
+                    view.x,
+                    view.y,
+                    view.x + view.width as i32,
+                    view.y + view.height as i32,
+                ] {
+                    assert_eq!(edge % 8, 0, "{view:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aspect_profile_follows_the_output_orientation() {
+    let mut grid = UniformGrid::new(Config::default());
+    assert_eq!(
+        layout(&mut grid, 4, 1920, 1080).layout_name,
+        "uniform-grid: 2x2"
+    );
+    assert_eq!(
+        layout(&mut grid, 4, 1080, 1920).layout_name,
+        "uniform-grid: 4x1"
+    );
+
+    cmd(&mut grid, "set-aspect-profile portrait=1:2 landscape=1:4");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (6, 6, 472, 1068),
+            (484, 6, 472, 1068),
+            (963, 6, 472, 1068),
+            (1441, 6, 472, 1068),
+        ],
+    );
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1080, 1920).views),
+        [
+            (6, 6, 531, 951),
+            (543, 6, 531, 951),
+            (543, 963, 531, 951),
+            (6, 963, 531, 951),
+        ],
+    );
+
+    cmd(&mut grid, "set-aspect-profile none");
+    assert_eq!(
+        layout(&mut grid, 4, 1920, 1080).layout_name,
+        "uniform-grid: 2x2"
+    );
+    assert!(matches!(
+        grid.user_cmd("set-aspect-profile square=1".into(), None, "test"),
+        Err(Error::InvalidArgument("profile")),
+    ));
+}
+
+#[test]
+fn max_fps_paces_steps_across_rapid_requests() {
+    let mut grid = UniformGrid::new(Config::default());
+    let start = Instant::now();
+    let steps = |pacer: &mut FramePacer| -> Vec<u64> {
+        (0..200)
+            .filter(|&ms| pacer.step(start + Duration::from_millis(ms)))
+            .collect()
+    };
+
+    assert_eq!(steps(&mut grid.frame_pacer()).len(), 200);
+
+    cmd(&mut grid, "set-max-fps 20");
+    assert_eq!(steps(&mut grid.frame_pacer()), [0, 50, 100, 150]);
+
+    cmd(&mut grid, "set-max-fps none");
+    assert_eq!(steps(&mut grid.frame_pacer()).len(), 200);
+    assert!(matches!(
+        grid.user_cmd("set-max-fps 0".into(), None, "test"),
+        Err(Error::InvalidArgument("fps")),
+    ));
+}
+
+#[test]
+fn swapped_views_exchange_cells() {
+    let mut grid = UniformGrid::new(Config::default());
+    let cells = [
+        (6, 6, 951, 531),
+        (963, 6, 951, 531),
+        (963, 543, 951, 531),
+        (6, 543, 951, 531),
+    ];
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), cells);
+
+    cmd(&mut grid, "swap 0 3");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [cells[3], cells[1], cells[2], cells[0]],
+    );
+
+    // Swaps persist, and are applied in order.
+    cmd(&mut grid, "swap 0 1");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [cells[1], cells[3], cells[2], cells[0]],
+    );
+    // Swaps with views that aren't there are skipped.
+    let three = rects(&layout(&mut UniformGrid::new(Config::default()), 3, 1920, 1080).views);
+    assert_eq!(
+        rects(&layout(&mut grid, 3, 1920, 1080).views),
+        [three[1], three[0], three[2]],
+    );
+
+    cmd(&mut grid, "clear-swaps");
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), cells);
+}
+
+#[test]
+fn commands_are_split_on_any_whitespace() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "\tset-aspect  1:4 \r\n");
+    assert_eq!(
+        layout(&mut grid, 4, 1920, 1080).layout_name,
+        "uniform-grid: 1x4"
+    );
+
+    for command in ["set-aspect\u{7}1", "set-aspect 1\0", "\u{1b}[Aset-aspect 1"] {
+        assert!(
+            matches!(
+                grid.user_cmd(command.into(), None, "test"),
+                Err(Error::InvalidArgument("command")),
+            ),
+            "{command:?}",
+        );
+    }
+    assert_eq!(grid.config.target_aspect, 0.25);
+}
+
+#[test]
+fn decoration_inset_leaves_room_at_the_top_of_each_cell() {
+    let mut grid = UniformGrid::new(Config::default());
+    let plain = rects(&layout(&mut grid, 4, 1920, 1080).views);
+
+    cmd(&mut grid, "set-decoration-inset 24");
+    let inset = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    for (plain, inset) in plain.iter().zip(&inset) {
+        assert_eq!(*inset, (plain.0, plain.1 + 24, plain.2, plain.3 - 24));
+    }
+    assert!(grid
+        .dump_commands()
+        .contains(&"set-decoration-inset 24".into()));
+
+    for invalid in ["set-decoration-inset -1", "set-decoration-inset 32768"] {
+        assert!(matches!(
+            grid.user_cmd(invalid.into(), None, "test"),
+            Err(Error::InvalidArgument("inset")),
+        ));
+    }
+    assert!(matches!(
+        grid.user_cmd("set-decoration-inset".into(), None, "test"),
+        Err(Error::MissingArgument("inset")),
+    ));
+}
+
+#[test]
+fn cached_layouts_match_fresh_ones_until_a_command() {
+    let mut grid = UniformGrid::new(Config::default());
+    let fresh = rects(&layout(&mut grid, 6, 1920, 1080).views);
+    assert_eq!(fresh[0], (6, 6, 632, 531));
+
+    let cached = grid.cached(6, 1920, 1080, 1, "test").unwrap();
+    assert_eq!(rects(&cached.views), fresh);
+    assert_eq!(rects(&layout(&mut grid, 6, 1920, 1080).views), fresh);
+    assert!(grid.cached(5, 1920, 1080, 1, "test").is_none());
+    assert!(grid.cached(6, 1920, 1080, 1, "other").is_none());
+
+    cmd(&mut grid, "set-aspect 1:4");
+    assert!(grid.cached(6, 1920, 1080, 1, "test").is_none());
+    let mut changed = UniformGrid::new(Config {
+        target_aspect: 0.25,
+        ..Config::default()
+    });
+    assert_eq!(
+        rects(&layout(&mut grid, 6, 1920, 1080).views),
+        rects(&layout(&mut changed, 6, 1920, 1080).views),
+    );
+}
+
+#[test]
+fn sidebar_overflow_puts_the_extra_views_in_a_right_column() {
+    let mut grid = UniformGrid::new(Config::default());
+    let views = rects(&layout(&mut grid, 7, 1920, 1080).views);
+    assert_eq!(views[6], (6, 722, 632, 352));
+
+    // Two full rows of three, and the seventh view beside them.
+    cmd(&mut grid, "set-overflow sidebar");
+    assert_eq!(
+        rects(&layout(&mut grid, 7, 1920, 1080).views),
+        [
+            (6, 6, 472, 531),
+            (484, 6, 472, 531),
+            (963, 6, 472, 531),
+            (963, 543, 472, 531),
+            (484, 543, 472, 531),
+            (6, 543, 472, 531),
+            (1441, 6, 472, 1068),
+        ],
+    );
+
+    // Full grids have nothing to overflow.
+    assert_eq!(
+        rects(&layout(&mut grid, 6, 1920, 1080).views),
+        rects(&layout(&mut UniformGrid::new(Config::default()), 6, 1920, 1080).views),
+    );
+}
+
+#[test]
+fn grid_lines_inset_the_cells_and_name_their_size() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "show-grid-lines");
+    let lined = layout(&mut grid, 4, 1920, 1080);
+    assert_eq!(lined.layout_name, "uniform-grid: 2x2 grid, 951x531 cells");
+    assert_eq!(
+        rects(&lined.views),
+        [
+            (8, 8, 947, 527),
+            (965, 8, 947, 527),
+            (965, 545, 947, 527),
+            (8, 545, 947, 527),
+        ],
+    );
+
+    cmd(&mut grid, "show-grid-lines");
+    let plain = layout(&mut grid, 4, 1920, 1080);
+    assert_eq!(plain.layout_name, "uniform-grid: 2x2");
+    assert_eq!(plain.views[0].width, 951);
+}
+
+#[test]
+fn reset_view_clears_transient_state_only() {
+    let mut grid = UniformGrid::new(Config::default());
+    let configured = [
+        (6, 6, 472, 1068),
+        (484, 6, 472, 1068),
+        (963, 6, 472, 1068),
+        (1441, 6, 472, 1068),
+    ];
+    cmd(&mut grid, "set-aspect 1:4");
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), configured);
+
+    for command in ["maximize 1", "swap 0 3", "show-grid-lines"] {
+        cmd(&mut grid, command);
+        assert_ne!(
+            rects(&layout(&mut grid, 4, 1920, 1080).views),
+            configured,
+            "{command}",
+        );
+    }
+
+    cmd(&mut grid, "reset-view");
+    assert_eq!(grid.config.target_aspect, 0.25);
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), configured);
+}
+
+#[test]
+fn aspect_bias_leans_toward_wider_or_taller_cells() {
+    let biased = |bias: &str, view_count| {
+        let mut grid = UniformGrid::new(Config::default());
+        cmd(&mut grid, &format!("set-aspect-bias {bias}"));
+        layout(&mut grid, view_count, 1920, 1080)
+    };
+
+    let wide = biased("1", 6);
+    assert_eq!(wide.layout_name, "uniform-grid: 3x2");
+    assert_eq!(rects(&wide.views)[0], (6, 6, 951, 352));
+    let tall = biased("-1", 6);
+    assert_eq!(tall.layout_name, "uniform-grid: 1x6");
+    assert_eq!(rects(&tall.views)[0], (6, 6, 313, 1068));
+
+    let cell_aspect =
+        |layout: &GeneratedLayout| layout.views[0].width as f32 / layout.views[0].height as f32;
+    for view_count in 2..13 {
+        let wider = cell_aspect(&biased("0.5", view_count));
+        let neutral = cell_aspect(&biased("0", view_count));
+        let taller = cell_aspect(&biased("-0.5", view_count));
+        assert!(wider >= neutral && neutral >= taller, "{view_count} views");
+    }
+
+    assert!(matches!(
+        UniformGrid::new(Config::default()).user_cmd("set-aspect-bias 2".into(), None, "test"),
+        Err(Error::InvalidArgument("bias")),
+    ));
+}
+
+#[test]
+fn huge_outputs_are_laid_out_at_the_largest_size() {
+    let padded = || Config {
+        outer_padding: 10,
+        ..Config::default()
+    };
+    let setups: [(Config, &[&str]); 5] = [
+        (padded(), &[]),
+        (padded(), &["maximize 0"]),
+        (
+            padded(),
+            &["set-master-ratio 0.5", "set-master-location bottom"],
+        ),
+        (
+            Config {
+                view_padding: i16::MIN.into(),
+                ..padded()
+            },
+            &["set-cell-size 1x1"],
+        ),
+        (padded(), &["set-wall 32767x32767", "center-last-row"]),
+    ];
+    for (config, setup) in setups {
+        let mut grid = UniformGrid::new(config);
+        for command in setup {
+            cmd(&mut grid, command);
+        }
+        let largest = layout(&mut grid, 5, MAX_USABLE_SIZE, MAX_USABLE_SIZE);
+        for size in [i32::MAX as u32 + 1, u32::MAX] {
+            let huge = layout(&mut grid, 5, size, size);
+            assert_eq!(rects(&huge.views), rects(&largest.views), "{setup:?}");
+        }
+    }
+}
+
+#[test]
+fn far_scrolled_views_saturate() {
+    let mut grid = UniformGrid::new(Config {
+        scroll_offset: -1e30,
+        global_grid_step: Some(3),
+        groups: vec![2],
+        ..Config::default()
+    });
+    let far = layout(&mut grid, 3, 1920, 1080);
+    assert_eq!(far.views.len(), 3);
+}
+
+#[test]
+fn view_counts_are_bounded() {
+    let setups: [&[&str]; 3] = [&[], &["set-overflow sidebar"], &["set-master-ratio 0.5"]];
+    for setup in setups {
+        let mut grid = UniformGrid::new(Config::default());
+        for command in setup {
+            cmd(&mut grid, command);
+        }
+        let most = layout(&mut grid, MAX_VIEW_COUNT, u32::MAX, u32::MAX);
+        assert_eq!(most.views.len(), MAX_VIEW_COUNT as usize, "{setup:?}");
+        assert!(matches!(
+            grid.generate_layout(u32::MAX, 1920, 1080, 1, "test"),
+            Err(Error::TooManyViews(u32::MAX)),
+        ));
+    }
+}
+
+#[test]
+fn region_confines_the_grid() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-region 50 0 50 100");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (966, 6, 471, 352),
+            (1443, 6, 471, 352),
+            (1443, 364, 471, 352),
+            (966, 364, 471, 352),
+        ],
+    );
+
+    // A region past the edges is trimmed to fit.
+    cmd(&mut grid, "set-region 25 50 100 100");
+    for (width, height) in OUTPUT_SIZES {
+        for view_count in 1..10 {
+            let views = layout(&mut grid, view_count, width, height).views;
+            for view in &views {
+                assert!(view.x >= (width / 4) as i32, "{view:?}");
+                assert!(view.y >= (height / 2) as i32, "{view:?}");
+            }
+            assert_within(&views, width, height);
+        }
+    }
+
+    cmd(&mut grid, "set-region none");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views)[0],
+        (6, 6, 951, 531),
+    );
+}
+
+#[test]
+fn last_layout_is_the_one_last_generated() {
+    let mut grid = UniformGrid::new(Config::default());
+    assert!(grid.last_layout().is_none());
+
+    layout(&mut grid, 6, 1920, 1080);
+    let generated = layout(&mut grid, 2, 1080, 1920);
+    let last = grid.last_layout().unwrap();
+    assert_eq!(last.layout_name, "uniform-grid: 2x1");
+    assert_eq!(rects(&last.views), [(6, 6, 1068, 951), (6, 963, 1068, 951)],);
+    assert_eq!(rects(&last.views), rects(&generated.views));
+}
+
+#[test]
+fn grid_aspect_fit_can_pick_a_different_grid() {
+    let mut grid = UniformGrid::new(Config::default());
+    let cell_aspect = layout(&mut grid, 8, 1920, 1080);
+    assert_eq!(cell_aspect.layout_name, "uniform-grid: 3x3");
+    assert_eq!(rects(&cell_aspect.views)[0], (6, 6, 632, 352));
+
+    cmd(&mut grid, "set-fit grid-aspect");
+    let grid_aspect = layout(&mut grid, 8, 1920, 1080);
+    assert_eq!(grid_aspect.layout_name, "uniform-grid: 2x4");
+    assert_eq!(
+        rects(&grid_aspect.views),
+        [
+            (6, 6, 472, 531),
+            (484, 6, 472, 531),
+            (963, 6, 472, 531),
+            (1441, 6, 472, 531),
+            (1441, 543, 472, 531),
+            (963, 543, 472, 531),
+            (484, 543, 472, 531),
+            (6, 543, 472, 531),
+        ],
+    );
+
+    cmd(&mut grid, "set-fit cell-aspect");
+    assert_eq!(
+        layout(&mut grid, 8, 1920, 1080).layout_name,
+        "uniform-grid: 3x3"
+    );
+}
+
+#[test]
+fn aliases_expand_to_their_commands() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "alias tall set-aspect 1:4");
+    cmd(&mut grid, "alias fit set-fit");
+
+    cmd(&mut grid, "tall");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (6, 6, 472, 1068),
+            (484, 6, 472, 1068),
+            (963, 6, 472, 1068),
+            (1441, 6, 472, 1068),
+        ],
+    );
+
+    // Arguments are passed on after the expansion.
+    cmd(&mut grid, "set-aspect 1");
+    cmd(&mut grid, "fit grid-aspect");
+    assert_eq!(
+        layout(&mut grid, 8, 1920, 1080).layout_name,
+        "uniform-grid: 2x4"
+    );
+    assert!(matches!(
+        grid.user_cmd("fit".into(), None, "test"),
+        Err(Error::MissingArgument("fit")),
+    ));
+}
+
+#[test]
+fn cell_size_determines_the_grid() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-cell-size 400x300");
+
+    // Four columns and three rows of whole cells fit within 1908x1068.
+    let full = layout(&mut grid, 12, 1920, 1080);
+    assert_eq!(full.layout_name, "uniform-grid: 3x4");
+    let mut cells = rects(&full.views);
+    cells.sort();
+    let expected: Vec<_> = [6, 412, 818, 1224]
+        .into_iter()
+        .flat_map(|x| [6, 312, 618].map(|y| (x, y, 400, 300)))
+        .collect();
+    assert_eq!(cells, expected);
+
+    // More views overflow onto another row, past the bottom of the output.
+    let overflowing = layout(&mut grid, 13, 1920, 1080);
+    assert_eq!(overflowing.layout_name, "uniform-grid: 4x4");
+    assert_eq!(overflowing.views[12].y, 924);
+
+    assert!(matches!(
+        grid.user_cmd("set-cell-size 0x300".into(), None, "test"),
+        Err(Error::InvalidArgument("size")),
+    ));
+}
+
+#[test]
+fn logical_padding_scales_with_the_output() {
+    let mut grid = UniformGrid::new(Config {
+        logical_padding: true,
+        ..Config::default()
+    });
+    cmd(&mut grid, "set-output-scale 2");
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (12, 12, 942, 522),
+            (966, 12, 942, 522),
+            (966, 546, 942, 522),
+            (12, 546, 942, 522),
+        ],
+    );
+
+    // Other outputs, and physical padding, are unscaled.
+    let other = grid.generate_layout(4, 1920, 1080, 1, "other").unwrap();
+    assert_eq!(rects(&other.views)[0], (6, 6, 951, 531));
+    let mut physical = UniformGrid::new(Config::default());
+    cmd(&mut physical, "set-output-scale 2");
+    assert_eq!(
+        rects(&layout(&mut physical, 4, 1920, 1080).views)[0],
+        (6, 6, 951, 531),
+    );
+}
+
+#[test]
+fn tag_filter_lays_out_only_the_active_tags_views() {
+    let mut grid = UniformGrid::new(Config::default());
+    for (index, tags) in [(0, 1), (1, 2), (2, 1), (3, 2), (4, 3)] {
+        cmd(&mut grid, &format!("set-view-tags {index} {tags}"));
+    }
+    cmd(&mut grid, "tag-filter");
+
+    // Views on both tags are shown either way, and views without any known
+    // tags (the sixth) are too.
+    let hidden = (0, 0, 0, 0);
+    let first = grid.generate_layout(6, 1920, 1080, 1, "test").unwrap();
+    assert_eq!(
+        rects(&first.views),
+        [
+            (6, 6, 951, 531),
+            hidden,
+            (963, 6, 951, 531),
+            hidden,
+            (963, 543, 951, 531),
+            (6, 543, 951, 531),
+        ],
+    );
+    let second = grid.generate_layout(4, 1920, 1080, 2, "test").unwrap();
+    assert_eq!(
+        rects(&second.views),
+        [hidden, (6, 6, 951, 1068), hidden, (963, 6, 951, 1068)],
+    );
+
+    cmd(&mut grid, "tag-filter");
+    assert_eq!(
+        rects(
+            &grid
+                .generate_layout(4, 1920, 1080, 2, "test")
+                .unwrap()
+                .views
+        )[0],
+        (6, 6, 951, 531),
+    );
+}
+
+#[test]
+fn describe_summarizes_the_last_layout() {
+    let mut grid = UniformGrid::new(Config::default());
+    assert_eq!(grid.describe(), "uniform-grid: no grid, 0 views");
+
+    let views = layout(&mut grid, 5, 1920, 1080).views;
+    assert_eq!(rects(&views)[0], (6, 6, 632, 531));
+    assert_eq!(
+        grid.describe(),
+        "uniform-grid: 2x3 grid, cells 632x531, 5 views, snake fill",
+    );
+
+    cmd(&mut grid, "set-fill-order row-major");
+    layout(&mut grid, 9, 1920, 1080);
+    assert_eq!(
+        grid.describe(),
+        "uniform-grid: 3x3 grid, cells 632x352, 9 views, row-major fill",
+    );
+}
+
+#[test]
+fn header_gap_only_separates_the_first_two_rows() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-header-gap 30");
+    cmd(&mut grid, "set-fill-order row-major");
+    let views = rects(&layout(&mut grid, 9, 1920, 1080).views);
+    let expected: Vec<_> = [6, 384, 732]
+        .into_iter()
+        .flat_map(|y| [6, 644, 1282].map(|x| (x, y, 632, 342)))
+        .collect();
+    assert_eq!(views, expected);
+
+    let gap =
+        |upper: usize, lower: usize| views[lower].1 - (views[upper].1 + views[upper].3 as i32);
+    assert_eq!(gap(0, 3), 6 + 30);
+    assert_eq!(gap(3, 6), 6);
+}
+
+#[test]
+fn reserved_cells_are_left_empty() {
+    let cell = |column: i32, row: i32| (6 + 638 * column, 6 + 358 * row, 632, 352);
+
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-fill-order row-major");
+    cmd(&mut grid, "reserve-cell 2 2");
+    let views = layout(&mut grid, 8, 1920, 1080);
+    assert_eq!(views.layout_name, "uniform-grid: 3x3");
+    let expected: Vec<_> = (0..8).map(|index| cell(index % 3, index / 3)).collect();
+    assert_eq!(rects(&views.views), expected);
+
+    // Views flow around a reserved cell in the middle of the grid.
+    cmd(&mut grid, "reserve-cell 1 1");
+    assert_eq!(
+        rects(&layout(&mut grid, 8, 1920, 1080).views),
+        [
+            cell(0, 0),
+            cell(1, 0),
+            cell(2, 0),
+            cell(0, 1),
+            cell(2, 1),
+            cell(0, 2),
+            cell(1, 2),
+            cell(2, 2),
+        ],
+    );
+    assert!(!rects(&layout(&mut grid, 8, 1920, 1080).views).contains(&cell(1, 1)));
+}
+
+#[test]
+fn hashed_bytes_finish_with_their_fnv_hash() {
+    let mut bytes = HashedBytes::default();
+    bytes.write(b"a");
+    assert_eq!(bytes.finish(), 0xaf63dc4c8601ec8c);
+    assert_eq!(HashedBytes::default().finish(), 0xcbf29ce484222325);
+}
+
+#[test]
+fn equal_configs_hash_the_same() {
+    let default = HashedBytes::of(&Config::default());
+    assert_eq!(
+        default.finish(),
+        HashedBytes::of(&Config::default()).finish()
+    );
+    assert_ne!(
+        default.finish(),
+        HashedBytes::of(&Config {
+            target_aspect: 1.0,
+            ..Config::default()
+        })
+        .finish()
+    );
+    assert!(Config::default() == Config::default());
+    assert!(
+        Config::default()
+            != Config {
+                target_aspect: 1.0,
+                ..Config::default()
+            }
+    );
+}
+
+#[test]
+fn exported_rules_place_views_in_their_cells() {
+    let mut grid = UniformGrid::new(Config::default());
+    assert!(grid.export_rules().is_empty());
+
+    layout(&mut grid, 2, 1920, 1080);
+    assert_eq!(
+        grid.export_rules(),
+        [
+            "riverctl rule-add -app-id 'view-0' float",
+            "riverctl rule-add -app-id 'view-0' position 6 6",
+            "riverctl rule-add -app-id 'view-0' dimensions 951 1068",
+            "riverctl rule-add -app-id 'view-1' float",
+            "riverctl rule-add -app-id 'view-1' position 963 6",
+            "riverctl rule-add -app-id 'view-1' dimensions 951 1068",
+        ],
+    );
+}
+
+/// Every coordinate of the views, in order, as little-endian bytes.
+fn layout_bytes(views: &[(i32, i32, u32, u32)]) -> Vec<u8> {
+    views
+        .iter()
+        .flat_map(|&(x, y, width, height)| {
+            [
+                x.to_le_bytes(),
+                y.to_le_bytes(),
+                width.to_le_bytes(),
+                height.to_le_bytes(),
+            ]
+        })
+        .flatten()
+        .collect()
+}
+
+/// Checks that fresh grids with the tie-break `seed` lay out `view_count`
+/// views on a square output into exactly the `expected` bytes.
+fn assert_reproduced(seed: &str, view_count: u32, size: &str, expected: &[(i32, i32, u32, u32)]) {
+    for _ in 0..2 {
+        let mut grid = UniformGrid::new(Config {
+            fit: Fit::GridAspect,
+            ..Config::default()
+        });
+        cmd(&mut grid, &format!("set-tie-break-seed {seed}"));
+        let generated = layout(&mut grid, view_count, 1000, 1000);
+        assert_eq!(generated.layout_name, format!("uniform-grid: {size}"));
+        assert_eq!(
+            layout_bytes(&rects(&generated.views)),
+            layout_bytes(expected),
+            "seed {seed}",
+        );
+    }
+}
+
+#[test]
+fn tie_break_seed_reproduces_the_same_layouts() {
+    // Both grid shapes fit a square output equally well.
+    assert_reproduced("none", 2, "1x2", &[(6, 6, 491, 988), (503, 6, 491, 988)]);
+    assert_reproduced("2", 2, "2x1", &[(6, 6, 988, 491), (6, 503, 988, 491)]);
+    assert_reproduced(
+        "none",
+        6,
+        "2x3",
+        &[
+            (6, 6, 325, 491),
+            (337, 6, 325, 491),
+            (668, 6, 325, 491),
+            (668, 503, 325, 491),
+            (337, 503, 325, 491),
+            (6, 503, 325, 491),
+        ],
+    );
+    assert_reproduced(
+        "3",
+        6,
+        "3x2",
+        &[
+            (6, 6, 491, 325),
+            (503, 6, 491, 325),
+            (503, 337, 491, 325),
+            (6, 337, 491, 325),
+            (6, 668, 491, 325),
+            (503, 668, 491, 325),
+        ],
+    );
+}
+
+#[test]
+fn walls_keep_their_grid_whatever_the_view_count() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-wall 4x4");
+    let mut cells: Vec<_> = [6, 274, 543, 811]
+        .into_iter()
+        .flat_map(|y| [6, 484, 963, 1441].map(|x| (x, y, 472, 262)))
+        .collect();
+    cells.sort();
+
+    for view_count in [1, 5, 16] {
+        let wall = layout(&mut grid, view_count, 1920, 1080);
+        assert_eq!(wall.layout_name, "uniform-grid: 4x4");
+        for view in rects(&wall.views) {
+            assert!(cells.contains(&view), "{view:?}");
+        }
+    }
+    let mut full = rects(&layout(&mut grid, 16, 1920, 1080).views);
+    full.sort();
+    assert_eq!(full, cells);
+
+    // Views past the sixteenth are hidden.
+    let overflowing = rects(&layout(&mut grid, 18, 1920, 1080).views);
+    assert_eq!(
+        overflowing[..16],
+        rects(&layout(&mut grid, 16, 1920, 1080).views)
+    );
+    assert_eq!(overflowing[16..], [(0, 0, 0, 0), (0, 0, 0, 0)]);
+}
+
+#[test]
+fn state_errors_describe_the_state_and_change_nothing() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "swap 0 1");
+    let before = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    assert_eq!(before[0], (963, 6, 951, 531));
+    let hash = grid.state_hash();
+
+    let error = grid.user_cmd("restore".into(), None, "test").unwrap_err();
+    assert_eq!(error.to_string(), "cannot restore: no view is maximized");
+    assert_eq!(grid.state_hash(), hash);
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), before);
+}
+
+#[test]
+fn transitions_step_at_most_max_fps_times_per_second() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-transition-frames 3");
+    cmd(&mut grid, "set-max-fps 20");
+    layout(&mut grid, 1, 1920, 1080);
+
+    // Each frame is recorded with when the layout that stepped to it started
+    // and finished, since it steps somewhere in between.
+    let interval = Duration::from_millis(50);
+    let start = Instant::now();
+    let views = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    let mut frames = vec![(start, Instant::now(), views)];
+    while grid.needs_redraw() && start.elapsed() < 20 * interval {
+        let before = Instant::now();
+        let views = rects(&layout(&mut grid, 4, 1920, 1080).views);
+        if views != frames.last().unwrap().2 {
+            frames.push((before, Instant::now(), views));
+        }
+    }
+
+    assert!(!grid.needs_redraw());
+    assert_eq!(frames.len(), 3);
+    for pair in frames.windows(2) {
+        assert!(pair[1].1 - pair[0].0 >= interval);
+    }
+    let mut still = UniformGrid::new(Config::default());
+    assert_eq!(frames[2].2, rects(&layout(&mut still, 4, 1920, 1080).views));
+}
+
+#[test]
+fn grid_changes_are_animated_between_the_cells() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-transition-frames 4");
+    let from = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    assert_eq!(
+        from,
+        [
+            (6, 6, 951, 531),
+            (963, 6, 951, 531),
+            (963, 543, 951, 531),
+            (6, 543, 951, 531),
+        ],
+    );
+    assert!(!grid.needs_redraw());
+
+    cmd(&mut grid, "set-aspect 1:4");
+    let mut frames = Vec::new();
+    loop {
+        frames.push(rects(&layout(&mut grid, 4, 1920, 1080).views));
+        if !grid.needs_redraw() {
+            break;
+        }
+    }
+    let to = [
+        (6, 6, 472, 1068),
+        (484, 6, 472, 1068),
+        (963, 6, 472, 1068),
+        (1441, 6, 472, 1068),
+    ];
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[3], to);
+    assert_eq!(frames[1][3], (724, 275, 712, 800));
+
+    let between = |value: i64, from: i64, to: i64| from.min(to) <= value && value <= from.max(to);
+    for frame in &frames[..3] {
+        for ((view, from), to) in frame.iter().zip(&from).zip(&to) {
+            assert!(
+                between(view.0.into(), from.0.into(), to.0.into()),
+                "{view:?}"
+            );
+            assert!(
+                between(view.1.into(), from.1.into(), to.1.into()),
+                "{view:?}"
+            );
+            assert!(
+                between(view.2.into(), from.2.into(), to.2.into()),
+                "{view:?}"
+            );
+            assert!(
+                between(view.3.into(), from.3.into(), to.3.into()),
+                "{view:?}"
+            );
+            assert!(view != from && view != to, "{view:?}");
+        }
+    }
+}
+
+#[test]
+fn state_hash_changes_with_the_layout() {
+    let mut grid = UniformGrid::new(Config::default());
+    let before = rects(&layout(&mut grid, 4, 1920, 1080).views);
+    let hash = grid.state_hash();
+    assert_eq!(format!("{hash:016x}").len(), 16);
+    cmd(&mut grid, "state-hash");
+    assert_eq!(grid.state_hash(), hash);
+
+    cmd(&mut grid, "swap 0 1");
+    assert_ne!(grid.state_hash(), hash);
+    assert_eq!(
+        rects(&layout(&mut grid, 4, 1920, 1080).views),
+        [
+            (963, 6, 951, 531),
+            (6, 6, 951, 531),
+            (963, 543, 951, 531),
+            (6, 543, 951, 531),
+        ],
+    );
+
+    // Getting back to the same state gives the same hash and layout.
+    cmd(&mut grid, "clear-swaps");
+    assert_eq!(grid.state_hash(), hash);
+    assert_eq!(rects(&layout(&mut grid, 4, 1920, 1080).views), before);
+
+    // So does the same state reached on a fresh instance.
+    let mut fresh = UniformGrid::new(Config::default());
+    layout(&mut fresh, 4, 1920, 1080);
+    assert_eq!(fresh.state_hash(), hash);
+}
+
+#[test]
+fn target_aspect_picks_the_grid_for_a_view_count() {
+    let mut grid = UniformGrid::new(Config::default());
+    let generated = layout(&mut grid, 6, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 2x3");
+    assert_eq!(rects(&generated.views[..1]), [(6, 6, 632, 531)]);
+
+    cmd(&mut grid, "set-aspect 1 2");
+    let generated = layout(&mut grid, 6, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 2x5");
+    assert_eq!(rects(&generated.views[..1]), [(6, 6, 376, 531)]);
+    assert_eq!(grid.config.target_aspect, 0.5);
+
+    cmd(&mut grid, "set-aspect 4");
+    let generated = layout(&mut grid, 6, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 3x2");
+    assert_eq!(rects(&generated.views[..1]), [(6, 6, 951, 352)]);
+
+    for invalid in ["set-aspect 0", "set-aspect -1:2", "set-aspect 1 0"] {
+        assert!(matches!(
+            grid.user_cmd(invalid.into(), None, "test"),
+            Err(Error::InvalidArgument("aspect")),
+        ));
+    }
+    assert_eq!(grid.config.target_aspect, 4.0);
+}
+
+#[test]
+fn fixed_columns_leave_the_last_row_partly_empty() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-columns 3");
+    let generated = layout(&mut grid, 7, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 3x3");
+    assert_eq!(
+        rects(&generated.views),
+        [
+            (6, 6, 632, 352),
+            (644, 6, 632, 352),
+            (1282, 6, 632, 352),
+            (1282, 364, 632, 352),
+            (644, 364, 632, 352),
+            (6, 364, 632, 352),
+            (6, 722, 632, 352),
+        ],
+    );
+
+    cmd(&mut grid, "set-columns 7");
+    let generated = layout(&mut grid, 7, 1920, 1080);
+    assert_eq!(generated.layout_name, "uniform-grid: 1x7");
+    assert_eq!(rects(&generated.views[..1]), [(6, 6, 267, 1068)]);
+
+    cmd(&mut grid, "set-columns auto");
+    let auto = layout(&mut grid, 7, 1920, 1080);
+    assert_eq!(
+        rects(&auto.views),
+        rects(&layout(&mut UniformGrid::new(Config::default()), 7, 1920, 1080).views),
+    );
+
+    for invalid in ["set-columns 0", "set-columns -2", "set-columns many"] {
+        assert!(matches!(
+            grid.user_cmd(invalid.into(), None, "test"),
+            Err(Error::InvalidArgument("columns")),
+        ));
+    }
+}
+
+#[test]
+fn centered_last_rows_are_centered_whatever_their_direction() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-columns 3");
+    cmd(&mut grid, "center-last-row");
+    assert_eq!(
+        rects(&layout(&mut grid, 5, 1920, 1080).views),
+        [
+            (6, 6, 632, 531),
+            (644, 6, 632, 531),
+            (1282, 6, 632, 531),
+            (963, 543, 632, 531),
+            (325, 543, 632, 531),
+        ],
+    );
+
+    // The last row of seven views runs the other way.
+    let views = rects(&layout(&mut grid, 7, 1920, 1080).views);
+    assert_eq!(views[6], (644, 722, 632, 352));
+
+    // Full rows are left as they are.
+    let full = rects(&layout(&mut grid, 6, 1920, 1080).views);
+    cmd(&mut grid, "center-last-row");
+    assert_eq!(rects(&layout(&mut grid, 6, 1920, 1080).views), full);
+    assert_eq!(
+        rects(&layout(&mut grid, 5, 1920, 1080).views)[3..],
+        [(1282, 543, 632, 531), (644, 543, 632, 531),]
+    );
+}
+
+#[test]
+fn fill_order_maps_views_to_cells() {
+    let cell = |column: i32, row: i32| (6 + 638 * column, 6 + 537 * row, 632, 531);
+    let orders = [
+        ("snake", [(0, 0), (1, 0), (2, 0), (2, 1), (1, 1)]),
+        ("row-major", [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)]),
+        ("column-major", [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]),
+    ];
+    for (order, cells) in orders {
+        let mut grid = UniformGrid::new(Config::default());
+        cmd(&mut grid, &format!("set-fill-order {order}"));
+        let expected: Vec<_> = cells
+            .into_iter()
+            .map(|(column, row)| cell(column, row))
+            .collect();
+        assert_eq!(
+            rects(&layout(&mut grid, 5, 1920, 1080).views),
+            expected,
+            "{order}",
+        );
+    }
+
+    let mut grid = UniformGrid::new(Config::default());
+    assert!(matches!(
+        grid.user_cmd("set-fill-order diagonal".into(), None, "test"),
+        Err(Error::InvalidArgument("order")),
+    ));
+}
+
+#[test]
+fn matching_the_output_aspect_transposes_grids_on_portrait_outputs() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "match-output-aspect");
+    let landscape = layout(&mut grid, 6, 1920, 1080);
+    assert_eq!(landscape.layout_name, "uniform-grid: 2x3");
+    assert_eq!(rects(&landscape.views[..1]), [(6, 6, 632, 531)]);
+
+    let portrait = layout(&mut grid, 6, 1080, 1920);
+    assert_eq!(portrait.layout_name, "uniform-grid: 3x2");
+    assert_eq!(rects(&portrait.views[..1]), [(6, 6, 531, 632)]);
+
+    // The fixed target aspect would keep landscape cells instead.
+    cmd(&mut grid, "match-output-aspect");
+    let fixed = layout(&mut grid, 6, 1080, 1920);
+    assert_eq!(fixed.layout_name, "uniform-grid: 4x2");
+}
+
+#[test]
+fn master_area_leaves_the_grid_the_rest_of_the_output() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-master-ratio 0.5");
+    let views = rects(&layout(&mut grid, 5, 1920, 1080).views);
+    assert_eq!(
+        views,
+        [
+            (6, 6, 954, 1068),
+            (966, 6, 471, 352),
+            (1443, 6, 471, 352),
+            (1443, 364, 471, 352),
+            (966, 364, 471, 352),
+        ],
+    );
+
+    // The stack is the grid of an output the size of the remaining area.
+    let stack = rects(&layout(&mut UniformGrid::new(Config::default()), 4, 960, 1080).views);
+    let shifted: Vec<_> = stack
+        .into_iter()
+        .map(|(x, y, width, height)| (x + 960, y, width, height))
+        .collect();
+    assert_eq!(views[1..], shifted);
+
+    cmd(&mut grid, "set-master-location bottom");
+    let views = rects(&layout(&mut grid, 5, 1920, 1080).views);
+    assert_eq!(views[0], (6, 540, 1908, 534));
+    assert_eq!(views[1], (6, 6, 632, 261));
+
+    // A lone view has the whole output.
+    assert_eq!(
+        rects(&layout(&mut grid, 1, 1920, 1080).views),
+        [(6, 6, 1908, 1068)],
+    );
+}
+
+#[test]
+fn master_view_is_finished_like_the_grid() {
+    let mut grid = UniformGrid::new(Config {
+        master_ratio: Some(0.5),
+        decoration_inset: 20,
+        global_grid_step: Some(10),
+        ..Config::default()
+    });
+    cmd(&mut grid, "show-grid-lines");
+    let views = rects(&layout(&mut grid, 5, 1920, 1080).views);
+    assert_eq!(views[0], (12, 32, 946, 1036));
+    assert_eq!(views[1], (972, 32, 466, 326));
+}
+
+#[test]
+fn degenerate_grids_rank_after_poorly_fitting_ones() {
+    let mut grid = UniformGrid::new(Config::default());
+    cmd(&mut grid, "set-aspect-bias -1");
+    let wide = layout(&mut grid, 2, 1920, 18);
+    assert_eq!(wide.layout_name, "uniform-grid: 1x2");
+    assert_eq!(rects(&wide.views), [(6, 6, 951, 6), (963, 6, 951, 6)]);
+}
@@ -0,0 +1,50 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+fn once(input: &str) -> (bool, Value) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_river-uniform-grid-layout"))
+        .arg("--once")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or(Value::Null);
+    (output.status.success(), json)
+}
+
+#[test]
+fn once_prints_a_single_layout() {
+    let view = |x: i32, y: i32| json!({"x": x, "y": y, "width": 632, "height": 531});
+    assert_eq!(
+        once("6 1920 1080 1 DP-1\n"),
+        (
+            true,
+            json!({
+                "layout_name": "uniform-grid: 2x3",
+                "views": [
+                    view(6, 6),
+                    view(644, 6),
+                    view(1282, 6),
+                    view(1282, 543),
+                    view(644, 543),
+                    view(6, 543),
+                ],
+            }),
+        ),
+    );
+}
+
+#[test]
+fn once_fails_on_missing_parameters() {
+    assert_eq!(once("6 1920 1080"), (false, Value::Null));
+}